@@ -0,0 +1,192 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crash;
+
+const CONFIG_FILE: &'static str = "reaping.cfg";
+
+/// A flat set of `key = value` settings, read from the user data directory
+/// and optionally overridden from the command line.
+#[derive(Clone)]
+pub struct Config {
+    values: HashMap<String, String>,
+
+    /// Command line values take precedence over the file, but are never saved
+    overrides: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn new() -> Config {
+        Config {
+            values: HashMap::new(),
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Loads the config file from the user data directory, or returns an empty
+    /// config if there isn't one yet.
+    pub fn load() -> Config {
+        let mut config = Config::new();
+
+        let file = match user_data_dir().map(|dir| dir.join(CONFIG_FILE)).and_then(|path| File::open(path).ok()) {
+            Some(file) => file,
+            None => return config,
+        };
+
+        for line in BufReader::new(file).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+
+            if let Some((key, value)) = parse_line(&line) {
+                config.values.insert(key, value);
+            }
+        }
+
+        config
+    }
+
+    /// Applies `--key=value` (or bare `--key`, meaning `true`) arguments as
+    /// overrides.  Returns any arguments that couldn't be understood.
+    pub fn apply_args<I>(&mut self, args: I) -> Vec<String> where I: Iterator<Item = String> {
+        let mut unrecognized = Vec::new();
+
+        for arg in args {
+            if !arg.starts_with("--") || arg.len() == 2 {
+                unrecognized.push(arg);
+                continue;
+            }
+
+            let (key, value) = match arg[2..].find('=') {
+                Some(index) => (arg[2..2 + index].to_string(), arg[3 + index..].to_string()),
+                None => (arg[2..].to_string(), "true".to_string()),
+            };
+
+            self.overrides.insert(key.replace('-', "_"), value);
+        }
+
+        unrecognized
+    }
+
+    pub fn save(&self) {
+        // Keep crash reports showing what the player has changed since launch
+        crash::set_config(self);
+
+        let dir = match user_data_dir() {
+            Some(dir) => dir,
+            None => {
+                warn!("No user data directory; config not saved");
+                return;
+            },
+        };
+
+        if let Err(error) = fs::create_dir_all(&dir) {
+            warn!("Could not create {}: {}", dir.display(), error);
+            return;
+        }
+
+        let mut keys: Vec<&String> = self.values.keys().collect();
+        keys.sort();
+
+        let result = File::create(dir.join(CONFIG_FILE)).and_then(|mut file| {
+            for key in keys {
+                try!(writeln!(file, "{} = {}", key, self.values[key]));
+            }
+            Ok(())
+        });
+
+        match result {
+            Ok(_) => debug!("Saved config to {}", dir.join(CONFIG_FILE).display()),
+            Err(error) => warn!("Could not save config: {}", error),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.overrides.get(key).or_else(|| self.values.get(key)).map(|value| value.as_str())
+    }
+
+    /// Returns the parsed value for key, or default if it's missing or malformed
+    pub fn get_or<T>(&self, key: &str, default: T) -> T where T: FromStr {
+        match self.get(key) {
+            Some(value) => match value.parse() {
+                Ok(value) => value,
+                Err(_) => {
+                    warn!("Ignoring malformed config value {} = {}", key, value);
+                    default
+                },
+            },
+            None => default,
+        }
+    }
+
+    /// Sets a value to be saved.  Clears any command line override for the key.
+    pub fn set<T>(&mut self, key: &str, value: T) where T: ToString {
+        self.overrides.remove(key);
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    /// Clears a value, along with any command line override
+    pub fn remove(&mut self, key: &str) {
+        self.overrides.remove(key);
+        self.values.remove(key);
+    }
+
+    /// Returns every key and effective value, sorted by key
+    pub fn entries(&self) -> Vec<(&str, &str)> {
+        let mut keys: Vec<&String> = self.values.keys().chain(self.overrides.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        keys.into_iter().map(|key| (key.as_str(), self.get(key).unwrap())).collect()
+    }
+}
+
+/// Splits a `key = value` line, skipping blank lines and # comments
+pub fn parse_line(line: &str) -> Option<(String, String)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    line.find('=').map(|index| (
+        line[..index].trim().to_string(),
+        line[index + 1..].trim().to_string(),
+    ))
+}
+
+/// Returns the per-user directory that holds the config, logs and saves
+pub fn user_data_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("The Reaping"))
+    } else if cfg!(target_os = "macos") {
+        env::home_dir().map(|dir| dir.join("Library/Application Support/The Reaping"))
+    } else {
+        env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+            .or_else(|| env::home_dir().map(|dir| dir.join(".local/share")))
+            .map(|dir| dir.join("reaping"))
+    }
+}
@@ -17,14 +17,22 @@
 // Copyright 2016 Chris Foster
 //
 
+use std::path::PathBuf;
+
 use sdl2;
 use sdl2_image;
 use sdl2_ttf;
 
+use config::Config;
 use graphics::{FontRenderer, RelativeCoordinator};
+use input::{Bindings, Controllers, InputTranslator};
 use math::Vector2;
+use random::Random;
+use timer::GameClock;
 
 pub struct Context {
+    pub config: Config,
+
     pub sdl_context: sdl2::Sdl,
     pub sdl_event_pump: sdl2::EventPump,
     pub sdl_video: sdl2::VideoSubsystem,
@@ -34,26 +42,52 @@ pub struct Context {
 
     pub font_renderer: FontRenderer,
 
+    pub input_translator: InputTranslator,
+
     pub screen_size: Vector2,
     pub rel: RelativeCoordinator,
 
     pub fps: u32,
+
+    pub clock: GameClock,
+
+    pub random: Random,
+
+    /// How far between the previous and current logic steps we're rendering, from 0 to 1
+    pub interpolation: f32,
+
+    /// Set when the previous run left a crash report the player should hear about
+    pub crash_report: Option<PathBuf>,
+
+    /// While a replay plays back, nothing is written to the player's saves
+    /// or config
+    pub replaying: bool,
+    /// Whether the main menu had Continue when the replay was recorded
+    pub replay_continue: bool,
 }
 
 impl Context {
-    pub fn new() -> Context {
+    pub fn new(config: Config) -> Context {
         let sdl_context = sdl2::init().unwrap();
         let sdl_event_pump = sdl_context.event_pump().unwrap();
         let sdl_video = sdl_context.video().unwrap();
 
-        // XXX Read window options out of a config file
-        let (width, height) = (1366, 768);
+        let (width, height) = (
+            config.get_or("window_width", 1366),
+            config.get_or("window_height", 768),
+        );
+        let fullscreen = config.get_or("fullscreen", false);
+        info!("Opening {}x{} window{}", width, height, if fullscreen { " (fullscreen)" } else { "" });
+
         let mut sdl_renderer = {
             let mut builder = sdl_video.window("The Reaping", width, height);
 
             builder.opengl();
+            builder.resizable();
 
-            //builder.fullscreen();
+            if fullscreen {
+                builder.fullscreen();
+            }
 
             let sdl_window = builder.build().unwrap();
             sdl_window.renderer().accelerated().build().unwrap()
@@ -76,7 +110,29 @@ impl Context {
 
         let rel = RelativeCoordinator::new(screen_size);
 
+        let controllers = {
+            let sdl_game_controller = match sdl_context.game_controller() {
+                Ok(subsystem) => Some(subsystem),
+                Err(error) => {
+                    warn!("Game controllers unavailable: {}", error);
+                    None
+                },
+            };
+
+            Controllers::new(sdl_game_controller, config.get_or("controller_deadzone", 0.35))
+        };
+
+        let input_translator = InputTranslator::new(Bindings::load(&config), controllers, screen_size);
+
+        let mut clock = GameClock::new();
+        clock.set_scale(config.get_or("time_scale", 1.0));
+
+        let seed = config.get_or("seed", Random::random_seed());
+        info!("Random seed {}", seed);
+
         Context {
+            config: config,
+
             sdl_context: sdl_context,
             sdl_event_pump: sdl_event_pump,
             sdl_video: sdl_video,
@@ -86,10 +142,60 @@ impl Context {
 
             font_renderer: font_renderer,
 
+            input_translator: input_translator,
+
             screen_size: screen_size,
             rel: rel,
 
             fps: 0,
+
+            clock: clock,
+
+            random: Random::new(seed),
+
+            interpolation: 1.0,
+
+            crash_report: None,
+
+            replaying: false,
+            replay_continue: false,
         }
     }
+
+    /// Matches the logical size to a resized window, so layouts can use the
+    /// extra room instead of being letterboxed
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        debug!("Window resized to {}x{}", width, height);
+        self.sdl_renderer.set_logical_size(width, height).ok();
+
+        self.screen_size = Vector2::new(width as f32, height as f32);
+        self.rel = RelativeCoordinator::new(self.screen_size);
+        self.input_translator.set_screen_size(self.screen_size);
+    }
+
+    /// Writes the config out, unless a replay is playing
+    pub fn save_config(&self) {
+        if self.replaying {
+            debug!("Not saving the config during a replay");
+            return;
+        }
+
+        self.config.save();
+    }
+
+    /// Makes the keyboard type text instead of triggering actions, until
+    /// stop_text_input.  See Input::Text.
+    pub fn start_text_input(&mut self) {
+        self.sdl_video.text_input().start();
+        self.input_translator.set_text_mode(true);
+    }
+
+    pub fn stop_text_input(&mut self) {
+        self.sdl_video.text_input().stop();
+        self.input_translator.set_text_mode(false);
+    }
 }
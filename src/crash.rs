@@ -0,0 +1,196 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use std::cell::RefCell;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::panic::{self, PanicInfo};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use backtrace::Backtrace;
+use time;
+
+use config::{self, Config};
+use log;
+use replay::Recorder;
+
+/// Holds the path of the last crash report until the next launch picks it up
+const PENDING_FILE: &'static str = "pending";
+
+/// What we know about the game's state, for inclusion in a crash report
+struct CrashState {
+    config: Vec<(String, String)>,
+    views: Vec<&'static str>,
+    /// The replay being recorded and where it goes
+    replay: Option<(Rc<RefCell<Recorder>>, PathBuf)>,
+}
+
+thread_local!(static STATE: RefCell<CrashState> = RefCell::new(CrashState {
+    config: Vec::new(),
+    views: Vec::new(),
+    replay: None,
+}));
+
+/// Installs a panic hook that writes a crash report to the user data
+/// directory before the default hook prints the panic.
+pub fn install(config: &Config) {
+    set_config(config);
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        match write_report(info) {
+            Some(path) => error!("Crash report written to {}", path.display()),
+            None => error!("Could not write crash report"),
+        }
+
+        save_replay();
+
+        default_hook(info);
+    }));
+}
+
+pub fn set_config(config: &Config) {
+    let entries = config.entries().into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    STATE.with(|cell| cell.borrow_mut().config = entries);
+}
+
+/// A crash is when a replay is wanted most, so the recorder is saved then too
+pub fn set_replay(recorder: Rc<RefCell<Recorder>>, path: PathBuf) {
+    STATE.with(|cell| cell.borrow_mut().replay = Some((recorder, path)));
+}
+
+/// Records the names of the views on the stack, topmost first
+pub fn set_views(views: Vec<&'static str>) {
+    STATE.with(|cell| cell.borrow_mut().views = views);
+}
+
+/// Returns the path of a crash report written during the previous run, if
+/// the player hasn't been told about it yet.
+pub fn take_pending_report() -> Option<PathBuf> {
+    let pending = match crash_dir() {
+        Some(dir) => dir.join(PENDING_FILE),
+        None => return None,
+    };
+
+    let mut contents = String::new();
+    let read = File::open(&pending).and_then(|mut file| file.read_to_string(&mut contents));
+    fs::remove_file(&pending).ok();
+
+    match read {
+        Ok(_) if !contents.trim().is_empty() => {
+            let path = PathBuf::from(contents.trim());
+            warn!("Previous run crashed; report at {}", path.display());
+            Some(path)
+        },
+        _ => None,
+    }
+}
+
+fn save_replay() {
+    // The panic may have happened while either was borrowed
+    STATE.with(|cell| if let Ok(state) = cell.try_borrow() {
+        if let Some((ref recorder, ref path)) = state.replay {
+            match recorder.try_borrow() {
+                Ok(recorder) => if let Err(error) = recorder.save(path) {
+                    error!("Could not save replay {}: {}", path.display(), error);
+                },
+                Err(_) => error!("Could not save replay {}; it was in use", path.display()),
+            }
+        }
+    });
+}
+
+fn crash_dir() -> Option<PathBuf> {
+    config::user_data_dir().map(|dir| dir.join("crashes"))
+}
+
+fn write_report(info: &PanicInfo) -> Option<PathBuf> {
+    let dir = match crash_dir() {
+        Some(dir) => dir,
+        None => return None,
+    };
+    fs::create_dir_all(&dir).ok();
+
+    let now = time::now();
+    let path = dir.join(format!(
+        "crash-{}.txt",
+        time::strftime("%Y%m%d-%H%M%S", &now).unwrap_or(String::new())
+    ));
+
+    let message = match info.payload().downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match info.payload().downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "Box<Any>".to_string(),
+        },
+    };
+
+    let location = match info.location() {
+        Some(location) => format!("{}:{}", location.file(), location.line()),
+        None => "unknown".to_string(),
+    };
+
+    let mut report = String::new();
+    report.push_str(&format!("The Reaping v{} ({}, {})\n",
+        env!("CARGO_PKG_VERSION"),
+        if cfg!(debug_assertions) { "debug" } else { "release" },
+        if cfg!(windows) { "windows" } else if cfg!(target_os = "macos") { "macos" } else { "linux" }
+    ));
+    report.push_str(&format!("Crashed at {}\n\n", time::strftime("%Y-%m-%d %H:%M:%S", &now).unwrap_or(String::new())));
+    report.push_str(&format!("Panic: {}\nLocation: {}\n\n", message, location));
+
+    // The panic may have happened while the state was borrowed
+    STATE.with(|cell| match cell.try_borrow() {
+        Ok(state) => {
+            report.push_str("Views (topmost first):\n");
+            for name in state.views.iter() {
+                report.push_str(&format!("    {}\n", name));
+            }
+
+            report.push_str("\nConfig:\n");
+            for &(ref key, ref value) in state.config.iter() {
+                report.push_str(&format!("    {} = {}\n", key, value));
+            }
+        },
+        Err(_) => report.push_str("Views and config unavailable\n"),
+    });
+
+    report.push_str(&format!("\nBacktrace:\n{:?}\n", Backtrace::new()));
+
+    report.push_str("\nRecent log:\n");
+    for line in log::recent_lines() {
+        report.push_str(&line);
+        report.push('\n');
+    }
+
+    let written = File::create(&path).and_then(|mut file| file.write_all(report.as_bytes()));
+    if written.is_err() {
+        return None;
+    }
+
+    File::create(dir.join(PENDING_FILE))
+        .and_then(|mut file| write!(file, "{}", path.display()))
+        .ok();
+
+    Some(path)
+}
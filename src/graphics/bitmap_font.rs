@@ -0,0 +1,257 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+//! Bitmap fonts in the AngelCode BMFont text format: a descriptor with one
+//! line per glyph giving its place in an atlas image, its offsets and its
+//! advance, plus kerning pairs.  Only single page fonts are supported.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::render::{BlendMode, Renderer, Texture};
+use sdl2::surface::Surface;
+use sdl2_image::LoadSurface;
+
+use graphics::Align;
+use math::{Rectangle, Vector2};
+
+struct BitmapGlyph {
+    region: Rectangle,
+    offset: Vector2,
+    advance: f32,
+}
+
+pub struct BitmapFont {
+    glyphs: HashMap<char, BitmapGlyph>,
+    kerning: HashMap<(char, char), f32>,
+    line_height: f32,
+
+    /// The whole number scale closest to the size the font was loaded at, so
+    /// pixels stay square
+    scale: f32,
+
+    surface: Surface<'static>,
+    /// Made from the surface the first time the font is drawn directly
+    texture: Option<Texture>,
+}
+
+impl BitmapFont {
+    pub fn load(path: &Path, size: u16) -> Result<BitmapFont, String> {
+        let mut descriptor = String::new();
+        try!(File::open(path).and_then(|mut file| file.read_to_string(&mut descriptor)).map_err(|error| error.to_string()));
+
+        let mut glyphs = HashMap::new();
+        let mut kerning = HashMap::new();
+        let mut line_height = 0.0;
+        let mut image = None;
+
+        for line in descriptor.lines() {
+            let tag = line.split_whitespace().next().unwrap_or("");
+            let values = parse_values(&line[tag.len()..]);
+            let number = |key: &str| values.iter().find(|&&(ref k, _)| k == key).and_then(|&(_, ref v)| v.parse::<i32>().ok()).unwrap_or(0);
+
+            match tag {
+                "common" => {
+                    line_height = number("lineHeight") as f32;
+                    if number("pages") > 1 {
+                        return Err("only single page bitmap fonts are supported".to_string());
+                    }
+                },
+                "page" => {
+                    image = values.iter().find(|&&(ref k, _)| k == "file").map(|&(_, ref file)| {
+                        path.parent().unwrap_or(Path::new("")).join(file)
+                    });
+                },
+                "char" => {
+                    let c = match ::std::char::from_u32(number("id") as u32) {
+                        Some(c) => c,
+                        None => continue,
+                    };
+
+                    glyphs.insert(c, BitmapGlyph {
+                        region: try!(Rectangle::new(
+                            Vector2::new(number("x") as f32, number("y") as f32),
+                            Vector2::new(number("width") as f32, number("height") as f32),
+                        ).ok_or(format!("bad size for glyph {}", number("id")))),
+                        offset: Vector2::new(number("xoffset") as f32, number("yoffset") as f32),
+                        advance: number("xadvance") as f32,
+                    });
+                },
+                "kerning" => {
+                    let first = ::std::char::from_u32(number("first") as u32);
+                    let second = ::std::char::from_u32(number("second") as u32);
+
+                    if let (Some(first), Some(second)) = (first, second) {
+                        kerning.insert((first, second), number("amount") as f32);
+                    }
+                },
+                _ => (),
+            }
+        }
+
+        if line_height <= 0.0 {
+            return Err("missing line height".to_string());
+        }
+
+        let image = try!(image.ok_or("missing page image".to_string()));
+        let surface = try!(Surface::from_file(&image));
+
+        Ok(BitmapFont {
+            glyphs: glyphs,
+            kerning: kerning,
+            line_height: line_height,
+
+            scale: (size as f32 / line_height).round().max(1.0),
+
+            surface: surface,
+            texture: None,
+        })
+    }
+
+    pub fn get_scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Where each glyph's pen position is, and the total advance, at native size
+    fn pen_positions(&self, text: &str) -> (Vec<(char, f32)>, f32) {
+        let chars: Vec<char> = text.chars().filter(|c| self.glyphs.contains_key(c)).collect();
+
+        let mut pen = 0.0;
+        let mut positions = Vec::with_capacity(chars.len());
+
+        for (index, c) in chars.iter().enumerate() {
+            positions.push((*c, pen));
+
+            pen += self.glyphs[c].advance;
+            if let Some(next) = chars.get(index + 1) {
+                pen += self.kerning.get(&(*c, *next)).cloned().unwrap_or(0.0);
+            }
+        }
+
+        (positions, pen)
+    }
+
+    /// The size text will be drawn at, scale included
+    pub fn size_of(&self, text: &str) -> Vector2 {
+        let (_, width) = self.pen_positions(text);
+        Vector2::new(width, self.line_height) * self.scale
+    }
+
+    /// Renders a line of white text at native size, to be tinted and scaled
+    /// once it's a texture
+    pub fn render_line(&self, text: &str) -> Option<Surface<'static>> {
+        let (positions, width) = self.pen_positions(text);
+        if width < 1.0 {
+            return None;
+        }
+
+        let mut surface = match Surface::new(width.ceil() as u32, self.line_height as u32, PixelFormatEnum::RGBA8888) {
+            Ok(surface) => surface,
+            Err(_) => return None,
+        };
+
+        for &(c, pen) in positions.iter() {
+            let glyph = &self.glyphs[&c];
+            if glyph.region.get_size().x < 1.0 || glyph.region.get_size().y < 1.0 {
+                continue;
+            }
+
+            let mut destination = glyph.region;
+            destination.position = Vector2::new(pen, 0.0) + glyph.offset;
+
+            self.surface.blit(Some(glyph.region.to_sdl_rectangle()), &mut surface, Some(destination.to_sdl_rectangle())).ok();
+        }
+
+        Some(surface)
+    }
+
+    /// Draws glyphs straight from the atlas image, like GlyphAtlas does for TTF fonts
+    pub fn draw(&mut self, sdl_renderer: &mut Renderer, text: &str, color: Color, position: Vector2, align: Align) {
+        if self.texture.is_none() {
+            self.texture = sdl_renderer.create_texture_from_surface(&self.surface).ok();
+            if let Some(ref mut texture) = self.texture {
+                texture.set_blend_mode(BlendMode::Blend);
+            }
+        }
+
+        let (positions, width) = self.pen_positions(text);
+        let scale = self.scale;
+
+        let start = Vector2::new(
+            match align {
+                Align::Left => position.x,
+                Align::Center => position.x - width * scale / 2.0,
+                Align::Right => position.x - width * scale,
+            }.round(),
+            (position.y - self.line_height * scale / 2.0).round(),
+        );
+
+        let texture = match self.texture {
+            Some(ref mut texture) => texture,
+            None => return,
+        };
+
+        let (r, g, b) = color.rgb();
+        texture.set_color_mod(r, g, b);
+
+        for &(c, pen) in positions.iter() {
+            let glyph = &self.glyphs[&c];
+            let size = glyph.region.get_size() * scale;
+
+            // Spaces have nothing to draw
+            let destination = match Rectangle::new(start + (Vector2::new(pen, 0.0) + glyph.offset) * scale, size) {
+                Some(destination) if size.x >= 1.0 && size.y >= 1.0 => destination,
+                _ => continue,
+            };
+
+            sdl_renderer.copy(texture, Some(glyph.region.to_sdl_rectangle()), Some(destination.to_sdl_rectangle()));
+        }
+    }
+}
+
+/// Splits `key=value key="quoted value"` pairs
+fn parse_values(line: &str) -> Vec<(String, String)> {
+    let mut values = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+        while chars.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            chars.next();
+        }
+
+        let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        if key.is_empty() {
+            break;
+        }
+
+        let value: String = if chars.peek() == Some(&'"') {
+            chars.next();
+            chars.by_ref().take_while(|c| *c != '"').collect()
+        } else {
+            chars.by_ref().take_while(|c| !c.is_whitespace()).collect()
+        };
+
+        values.push((key.trim().to_string(), value));
+    }
+
+    values
+}
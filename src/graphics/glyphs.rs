@@ -0,0 +1,287 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use std::collections::HashMap;
+
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect as SdlRectangle;
+use sdl2::render::{BlendMode, Renderer, Texture};
+use sdl2::surface::Surface;
+
+use context::Context;
+use graphics::{Align, Font, FontFace, FontRenderer, GraphicObject};
+use math::{Rectangle, Vector2};
+
+const ATLAS_SIZE: u32 = 1024;
+
+/// Space left around each glyph so scaling doesn't bleed its neighbours in
+const GLYPH_PADDING: u32 = 1;
+
+struct Glyph {
+    region: Rectangle,
+    advance: f32,
+}
+
+/// Every glyph of one font that's been drawn so far, packed in rows into a
+/// surface.  The texture is rebuilt from the surface when glyphs are added.
+pub struct GlyphAtlas {
+    surface: Surface<'static>,
+    texture: Option<Texture>,
+    dirty: bool,
+
+    glyphs: HashMap<char, Glyph>,
+    /// Advances that include kerning, by character pair
+    advances: HashMap<(char, char), f32>,
+
+    cursor: (u32, u32),
+    row_height: u32,
+}
+
+impl GlyphAtlas {
+    fn new() -> Option<GlyphAtlas> {
+        match Surface::new(ATLAS_SIZE, ATLAS_SIZE, PixelFormatEnum::RGBA8888) {
+            Ok(surface) => Some(GlyphAtlas {
+                surface: surface,
+                texture: None,
+                dirty: true,
+
+                glyphs: HashMap::new(),
+                advances: HashMap::new(),
+
+                cursor: (0, 0),
+                row_height: 0,
+            }),
+            Err(error) => {
+                warn!("Could not create glyph atlas: {}", error);
+                None
+            },
+        }
+    }
+
+    fn clear(&mut self) {
+        debug!("Glyph atlas full, starting over");
+
+        self.surface.fill_rect(None, Color::RGBA(0, 0, 0, 0)).ok();
+        self.glyphs.clear();
+        self.cursor = (0, 0);
+        self.row_height = 0;
+        self.dirty = true;
+    }
+
+    /// Rasterizes a glyph into the atlas.  Returns false if it's full.
+    fn add(&mut self, sdl_font: &::sdl2_ttf::Font, c: char) -> bool {
+        let mut glyph_surface = match sdl_font.render_char(c).blended(Color::RGB(255, 255, 255)) {
+            Ok(glyph_surface) => glyph_surface,
+            Err(_) => {
+                // Nothing to draw, but keep the space it takes up
+                let advance = sdl_font.find_glyph_metrics(c).map(|metrics| metrics.advance).unwrap_or(0);
+                self.glyphs.insert(c, Glyph {
+                    region: Rectangle::new(Vector2::zero(), Vector2::zero()).unwrap(),
+                    advance: advance as f32,
+                });
+                return true;
+            },
+        };
+
+        let (width, height) = (glyph_surface.width(), glyph_surface.height());
+
+        if self.cursor.0 + width + GLYPH_PADDING > ATLAS_SIZE {
+            self.cursor = (0, self.cursor.1 + self.row_height + GLYPH_PADDING);
+            self.row_height = 0;
+        }
+        if self.cursor.1 + height > ATLAS_SIZE {
+            return false;
+        }
+
+        glyph_surface.set_blend_mode(BlendMode::None).ok();
+        let destination = SdlRectangle::new(self.cursor.0 as i32, self.cursor.1 as i32, width, height).ok().and_then(|rect| rect);
+        glyph_surface.blit(None, &mut self.surface, destination).ok();
+
+        let advance = sdl_font.find_glyph_metrics(c).map(|metrics| metrics.advance as f32).unwrap_or(width as f32);
+
+        self.glyphs.insert(c, Glyph {
+            region: Rectangle::new(
+                Vector2::new(self.cursor.0 as f32, self.cursor.1 as f32),
+                Vector2::new(width as f32, height as f32),
+            ).unwrap(),
+            advance: advance,
+        });
+
+        self.cursor.0 += width + GLYPH_PADDING;
+        if height > self.row_height {
+            self.row_height = height;
+        }
+        self.dirty = true;
+
+        true
+    }
+
+    /// How far to move from c to next.  TTF measuring includes kerning, so
+    /// the pair's width less the second character's width is the kerned advance.
+    fn advance(&mut self, sdl_font: &::sdl2_ttf::Font, c: char, next: Option<char>) -> f32 {
+        let plain = self.glyphs.get(&c).map(|glyph| glyph.advance).unwrap_or(0.0);
+
+        let next = match next {
+            Some(next) => next,
+            None => return plain,
+        };
+
+        if let Some(advance) = self.advances.get(&(c, next)) {
+            return *advance;
+        }
+
+        let pair: String = vec![c, next].into_iter().collect();
+        let advance = match (sdl_font.size_of(&pair), sdl_font.size_of(&next.to_string())) {
+            (Ok((pair_width, _)), Ok((next_width, _))) => pair_width as f32 - next_width as f32,
+            _ => plain,
+        };
+
+        self.advances.insert((c, next), advance);
+        advance
+    }
+}
+
+impl FontRenderer {
+    /// Draws text from the font's glyph atlas without creating any new
+    /// textures, unless it has characters that haven't been drawn before.
+    /// position is where the text is aligned to, vertically centered.
+    pub fn draw_cached(&mut self, sdl_renderer: &mut Renderer, font: &Font, text: &str, color: Color, position: Vector2, align: Align) {
+        let sdl_font = match self.cached_fonts.get_mut(font) {
+            Some(&mut FontFace::Ttf(ref sdl_font)) => sdl_font,
+            Some(&mut FontFace::Bitmap(ref mut bitmap_font)) => {
+                // Bitmap fonts are already an atlas
+                bitmap_font.draw(sdl_renderer, text, color, position, align);
+                return;
+            },
+            None => return,
+        };
+
+        if !self.atlases.contains_key(font) {
+            match GlyphAtlas::new() {
+                Some(atlas) => { self.atlases.insert(font.clone(), atlas); },
+                None => return,
+            }
+        }
+        let atlas = self.atlases.get_mut(font).unwrap();
+
+        // Make sure every glyph is in, starting over once if the atlas fills up
+        let chars: Vec<char> = text.chars().collect();
+        for attempt in 0..2 {
+            let fits = chars.iter().all(|c| atlas.glyphs.contains_key(c) || atlas.add(sdl_font, *c));
+            if fits {
+                break;
+            } else if attempt == 0 {
+                atlas.clear();
+            } else {
+                warn!("Text doesn't fit in the glyph atlas: {}", text);
+                return;
+            }
+        }
+
+        if atlas.dirty {
+            atlas.texture = sdl_renderer.create_texture_from_surface(&atlas.surface).ok();
+            if let Some(ref mut texture) = atlas.texture {
+                texture.set_blend_mode(BlendMode::Blend);
+            }
+            atlas.dirty = false;
+        }
+
+        let advances: Vec<f32> = (0..chars.len()).map(|index| {
+            atlas.advance(sdl_font, chars[index], chars.get(index + 1).cloned())
+        }).collect();
+        let width = advances.iter().fold(0.0, |width, advance| width + advance);
+
+        let mut pen = Vector2::new(
+            match align {
+                Align::Left => position.x,
+                Align::Center => position.x - width / 2.0,
+                Align::Right => position.x - width,
+            }.round(),
+            (position.y - sdl_font.height() as f32 / 2.0).round(),
+        );
+
+        let texture = match atlas.texture {
+            Some(ref mut texture) => texture,
+            None => return,
+        };
+        let (r, g, b) = color.rgb();
+        texture.set_color_mod(r, g, b);
+
+        for (c, advance) in chars.iter().zip(advances.iter()) {
+            let glyph = &atlas.glyphs[c];
+
+            if glyph.region.get_size().x > 0.0 {
+                let mut destination = glyph.region;
+                destination.position = pen;
+
+                sdl_renderer.copy(texture, Some(glyph.region.to_sdl_rectangle()), Some(destination.to_sdl_rectangle()));
+            }
+
+            pen.x += *advance;
+        }
+    }
+}
+
+/// A line of text drawn from a glyph atlas, for text that changes often,
+/// like counters.  Changing it is cheap, unlike re-rendering a Sprite.
+pub struct Text {
+    pub position: Vector2,
+    pub align: Align,
+
+    font: Font,
+    text: String,
+    color: Color,
+}
+
+impl Text {
+    pub fn new(font: &Font, text: &str, color: Color) -> Text {
+        Text {
+            position: Vector2::zero(),
+            align: Align::Center,
+
+            font: font.clone(),
+            text: text.to_string(),
+            color: color,
+        }
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        if self.text != text {
+            self.text = text.to_string();
+        }
+    }
+
+    pub fn get_size(&self, context: &Context) -> Vector2 {
+        context.font_renderer.size_of(&self.font, &self.text).unwrap_or(Vector2::zero())
+    }
+}
+
+impl GraphicObject for Text {
+    fn draw(&self, context: &mut Context) {
+        if self.text.is_empty() {
+            return;
+        }
+
+        context.font_renderer.draw_cached(&mut context.sdl_renderer, &self.font, &self.text, self.color, self.position, self.align);
+    }
+}
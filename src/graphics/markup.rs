@@ -0,0 +1,384 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+//! A small markup language for text with inline styling:
+//!
+//! - `[color=#ff0]1UP[/color]` changes the colour, as #rgb or #rrggbb
+//! - `[b]bold[/b]` switches to the font's bold variant, see FontRenderer::set_bold
+//! - `[icon=name]` inserts an icon loaded with FontRenderer::load_icon
+//! - `[[` is a literal `[`
+//!
+//! Tags that aren't recognized are left in the text as they are.
+
+use std::path::Path;
+
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect as SdlRectangle;
+use sdl2::render::BlendMode;
+use sdl2::surface::Surface;
+use sdl2_image::LoadSurface;
+
+use context::Context;
+use graphics::{Align, Font, FontFace, FontRenderer, Sprite, TextStyle};
+use math::Vector2;
+
+/// A styled piece of markup
+#[derive(Clone, Debug, PartialEq)]
+pub enum Run {
+    Text {
+        text: String,
+        color: Color,
+        bold: bool,
+    },
+    Icon(String),
+    LineBreak,
+}
+
+/// Splits markup into runs, starting in color
+pub fn parse_markup(markup: &str, color: Color) -> Vec<Run> {
+    let mut runs = Vec::new();
+    let mut colors = vec![color];
+    let mut bold = 0;
+    let mut text = String::new();
+
+    // Ends the text run in progress, before the style changes
+    fn flush(runs: &mut Vec<Run>, text: &mut String, color: Color, bold: bool) {
+        if !text.is_empty() {
+            runs.push(Run::Text {
+                text: text.clone(),
+                color: color,
+                bold: bold,
+            });
+            text.clear();
+        }
+    }
+
+    let mut rest = markup;
+    while let Some(c) = rest.chars().next() {
+        let current = *colors.last().unwrap();
+
+        if c == '\n' {
+            flush(&mut runs, &mut text, current, bold > 0);
+            runs.push(Run::LineBreak);
+            rest = &rest[1..];
+            continue;
+        }
+
+        if c == '[' {
+            if rest.starts_with("[[") {
+                text.push('[');
+                rest = &rest[2..];
+                continue;
+            }
+
+            if let Some(end) = rest.find(']') {
+                let tag = &rest[1..end];
+                let mut recognized = true;
+
+                if tag.starts_with("color=") {
+                    match parse_color(&tag[6..]) {
+                        Some(color) => {
+                            flush(&mut runs, &mut text, current, bold > 0);
+                            colors.push(color);
+                        },
+                        None => recognized = false,
+                    }
+                } else if tag == "/color" && colors.len() > 1 {
+                    flush(&mut runs, &mut text, current, bold > 0);
+                    colors.pop();
+                } else if tag == "b" {
+                    flush(&mut runs, &mut text, current, bold > 0);
+                    bold += 1;
+                } else if tag == "/b" && bold > 0 {
+                    flush(&mut runs, &mut text, current, bold > 0);
+                    bold -= 1;
+                } else if tag.starts_with("icon=") {
+                    flush(&mut runs, &mut text, current, bold > 0);
+                    runs.push(Run::Icon(tag[5..].to_string()));
+                } else {
+                    recognized = false;
+                }
+
+                if recognized {
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+
+        text.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    flush(&mut runs, &mut text, *colors.last().unwrap(), bold > 0);
+    runs
+}
+
+/// Makes text safe to put in markup
+pub fn escape_markup(text: &str) -> String {
+    text.replace("[", "[[")
+}
+
+/// Parses #rgb or #rrggbb
+fn parse_color(value: &str) -> Option<Color> {
+    if !value.starts_with('#') || !value[1..].chars().all(|c| c.is_digit(16)) {
+        return None;
+    }
+
+    let digits = &value[1..];
+    let channel = |start: usize, length: usize| {
+        let channel = u8::from_str_radix(&digits[start..start + length], 16).unwrap_or(0);
+        if length == 1 { channel * 17 } else { channel }
+    };
+
+    match digits.len() {
+        3 => Some(Color::RGB(channel(0, 1), channel(1, 1), channel(2, 1))),
+        6 => Some(Color::RGB(channel(0, 2), channel(2, 2), channel(4, 2))),
+        _ => None,
+    }
+}
+
+/// A run rendered and ready to be placed on its line
+struct Piece<'a> {
+    surface: PieceSurface<'a>,
+    size: Vector2,
+}
+
+enum PieceSurface<'a> {
+    Text(Surface<'static>),
+    Icon(&'a Surface<'static>),
+}
+
+impl FontRenderer {
+    /// Makes [b] in text drawn with regular use bold instead
+    pub fn set_bold(&mut self, regular: &Font, bold: &Font) {
+        self.bold_fonts.insert(regular.clone(), bold.clone());
+    }
+
+    /// Loads an image that markup can show with [icon=name]
+    pub fn load_icon(&mut self, name: &'static str, path: &str) {
+        match Surface::from_file(Path::new(path)) {
+            Ok(mut surface) => {
+                debug!("Loaded icon {} from {}", name, path);
+
+                // Copied as is onto text surfaces, which start out transparent
+                surface.set_blend_mode(BlendMode::None).ok();
+                self.icons.insert(name, surface);
+            },
+            Err(error) => panic!("Could not load icon {}: {}", path, error),
+        }
+    }
+
+    /// Renders markup into one sprite.  Lines only break at newlines, so the
+    /// style's max_width and max_lines aren't used.  Icons are scaled to the
+    /// font's line height.
+    pub fn render_markup(&self, context: &Context, font: &Font, markup: &str, color: Color, style: &TextStyle) -> Option<Sprite> {
+        let line_height = match self.size_of(font, "Ay") {
+            Some(size) => size.y,
+            None => return None,
+        };
+
+        let mut lines: Vec<Vec<Piece>> = vec![Vec::new()];
+
+        for run in parse_markup(markup, color) {
+            let piece = match run {
+                Run::Text { ref text, color, bold } => {
+                    let run_font = if bold { self.bold_fonts.get(font).unwrap_or(font) } else { font };
+                    self.cached_fonts.get(run_font).and_then(|face| text_piece(face, text, color))
+                },
+                Run::Icon(ref name) => {
+                    match self.icons.get(&name[..]) {
+                        Some(icon) => icon_piece(icon, line_height),
+                        None => {
+                            warn!("Unknown icon in markup: {}", name);
+                            None
+                        },
+                    }
+                },
+                Run::LineBreak => {
+                    lines.push(Vec::new());
+                    None
+                },
+            };
+
+            if let Some(piece) = piece {
+                lines.last_mut().unwrap().push(piece);
+            }
+        }
+
+        let widths: Vec<f32> = lines.iter().map(|line| line.iter().fold(0.0, |width, piece| width + piece.size.x)).collect();
+        let heights: Vec<f32> = lines.iter().map(|line| {
+            line.iter().fold(line_height, |height, piece| if piece.size.y > height { piece.size.y } else { height })
+        }).collect();
+
+        let width = widths.iter().fold(0.0, |width, line_width| if *line_width > width { *line_width } else { width });
+        let height = heights.iter().enumerate().fold(0.0, |height, (index, line_height)| {
+            height + if index + 1 < heights.len() { (line_height * style.line_spacing).round() } else { *line_height }
+        });
+
+        if width < 1.0 || height < 1.0 {
+            return None;
+        }
+
+        let mut surface = match Surface::new(width.ceil() as u32, height.ceil() as u32, PixelFormatEnum::RGBA8888) {
+            Ok(surface) => surface,
+            Err(error) => {
+                warn!("Could not create markup surface: {}", error);
+                return None;
+            },
+        };
+
+        let mut y = 0.0;
+        for (index, line) in lines.iter().enumerate() {
+            let mut x = match style.align {
+                Align::Left => 0.0,
+                Align::Center => ((width - widths[index]) / 2.0).round(),
+                Align::Right => width - widths[index],
+            };
+
+            for piece in line.iter() {
+                let destination = SdlRectangle::new(
+                    x as i32,
+                    (y + (heights[index] - piece.size.y) / 2.0) as i32,
+                    piece.size.x as u32,
+                    piece.size.y as u32,
+                ).ok().and_then(|rect| rect);
+
+                match piece.surface {
+                    PieceSurface::Text(ref text) => text.blit_scaled(None, &mut surface, destination),
+                    PieceSurface::Icon(icon) => icon.blit_scaled(None, &mut surface, destination),
+                }.ok();
+                x += piece.size.x;
+            }
+
+            y += (heights[index] * style.line_spacing).round();
+        }
+
+        context.sdl_renderer.create_texture_from_surface(&surface).ok()
+            .map(|texture| Sprite::new(texture, None, None))
+    }
+}
+
+fn text_piece<'a>(face: &FontFace, text: &str, color: Color) -> Option<Piece<'a>> {
+    face.render_line(text, color).map(|mut surface| {
+        // Bitmap glyphs are white and at their native size
+        if let FontFace::Bitmap(_) = *face {
+            surface.set_color_mod(color);
+        }
+        surface.set_blend_mode(BlendMode::None).ok();
+
+        let size = Vector2::new(surface.width() as f32, surface.height() as f32) * face.get_scale();
+        Piece {
+            surface: PieceSurface::Text(surface),
+            size: size,
+        }
+    })
+}
+
+fn icon_piece<'a>(icon: &'a Surface<'static>, line_height: f32) -> Option<Piece<'a>> {
+    let (width, height) = (icon.width() as f32, icon.height() as f32);
+    if width < 1.0 || height < 1.0 {
+        return None;
+    }
+
+    Some(Piece {
+        surface: PieceSurface::Icon(icon),
+        size: Vector2::new((width * line_height / height).round(), line_height),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sdl2::pixels::Color;
+    use super::{parse_color, parse_markup, Run};
+
+    fn text(text: &str, color: Color, bold: bool) -> Run {
+        Run::Text {
+            text: text.to_string(),
+            color: color,
+            bold: bold,
+        }
+    }
+
+    #[test]
+    fn colors_are_rgb_or_rrggbb() {
+        assert_eq!(parse_color("#f80"), Some(Color::RGB(255, 136, 0)));
+        assert_eq!(parse_color("#ff8000"), Some(Color::RGB(255, 128, 0)));
+        assert_eq!(parse_color("#1a2B3c"), Some(Color::RGB(26, 43, 60)));
+
+        assert_eq!(parse_color("f80"), None);
+        assert_eq!(parse_color("#ff80"), None);
+        assert_eq!(parse_color("#ggg"), None);
+        assert_eq!(parse_color("#"), None);
+    }
+
+    #[test]
+    fn nested_tags_stack() {
+        let white = Color::RGB(255, 255, 255);
+        let red = Color::RGB(255, 0, 0);
+        let blue = Color::RGB(0, 0, 255);
+
+        assert_eq!(parse_markup("a[color=#f00]b[b]c[color=#00f]d[/color]e[/b]f[/color]g", white), vec![
+            text("a", white, false),
+            text("b", red, false),
+            text("c", red, true),
+            text("d", blue, true),
+            text("e", red, true),
+            text("f", red, false),
+            text("g", white, false),
+        ]);
+    }
+
+    #[test]
+    fn icons_and_newlines_split_runs() {
+        let white = Color::RGB(255, 255, 255);
+
+        assert_eq!(parse_markup("[icon=heart]x3\n[b][icon=key][/b]", white), vec![
+            Run::Icon("heart".to_string()),
+            text("x3", white, false),
+            Run::LineBreak,
+            Run::Icon("key".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn double_brackets_are_literal() {
+        let white = Color::RGB(255, 255, 255);
+
+        assert_eq!(parse_markup("[[b] and [[[b]bold", white), vec![
+            text("[b] and [", white, false),
+            text("bold", white, true),
+        ]);
+        assert_eq!(parse_markup("[[", white), vec![text("[", white, false)]);
+    }
+
+    #[test]
+    fn unknown_and_unclosed_tags_are_text() {
+        let white = Color::RGB(255, 255, 255);
+
+        assert_eq!(parse_markup("[u]x[/u] [/b][/color]", white), vec![text("[u]x[/u] [/b][/color]", white, false)]);
+        assert_eq!(parse_markup("[color=red]x[color=#f00", white), vec![text("[color=red]x[color=#f00", white, false)]);
+
+        // Styles left open run to the end
+        assert_eq!(parse_markup("a[b]b", white), vec![
+            text("a", white, false),
+            text("b", white, true),
+        ]);
+    }
+}
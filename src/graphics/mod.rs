@@ -23,41 +23,88 @@ use std::rc::Rc;
 
 use sdl2::pixels::Color;
 use sdl2::render::{Texture, TextureQuery};
+use sdl2::surface::Surface;
 use sdl2_image::LoadTexture;
 use sdl2_ttf::Font as SdlFont;
 use sdl2_ttf::Sdl2TtfContext;
 
 use context::Context;
 use math::{Rectangle, Transform, Vector2};
-use view::View;
+use view::{ViewStack, ViewTransition};
+
+use self::bitmap_font::BitmapFont;
+use self::glyphs::GlyphAtlas;
 
 pub trait GraphicObject {
     fn draw(&self, context: &mut Context);
 }
 
-pub struct GraphicRenderer;
+pub struct GraphicRenderer {
+    /// Drawn over everything when the show_fps config key is set
+    fps_text: Option<Text>,
+    shown_fps: u32,
+}
 
 impl GraphicRenderer {
-    pub fn new() -> GraphicRenderer {
-        GraphicRenderer
+    pub fn new(context: &mut Context) -> GraphicRenderer {
+        let fps_text = if context.config.get_or("show_fps", false) {
+            let font = context.font_renderer.load_font("assets/fonts/fff_aquarius_bold.ttf", context.rel.height(0.03) as u16);
+
+            let mut text = Text::new(&font, "", Color::RGB(255, 255, 255));
+            text.align = Align::Right;
+            Some(text)
+        } else {
+            None
+        };
+
+        GraphicRenderer {
+            fps_text: fps_text,
+            shown_fps: 0,
+        }
     }
 
-    pub fn render(&self, context: &mut Context, views: &Vec<Box<View>>) {
+    /// Interpolation is the fraction of a logic step that has passed since the
+    /// last update, used to draw moving objects between their previous and
+    /// current transforms.
+    pub fn render(&mut self, context: &mut Context, views: &ViewStack, transition: Option<&mut ViewTransition>, interpolation: f32) {
+        context.interpolation = interpolation;
+
         context.sdl_renderer.set_draw_color(Color::RGB(0, 0, 0));
         context.sdl_renderer.clear();
 
-        for view in views.iter().rev() {
-            let view_data = view.get_view_data();
+        match transition {
+            Some(transition) => transition.draw(context, views),
+            None => draw_views(context, views),
+        }
 
-            for graphic_object in view_data.graphic_objects.iter() {
-                graphic_object.draw(context);
+        if let Some(ref mut fps_text) = self.fps_text {
+            if context.fps != self.shown_fps || fps_text.get_text().is_empty() {
+                fps_text.set_text(&format!("{} FPS", context.fps));
+                self.shown_fps = context.fps;
             }
+
+            fps_text.position = Vector2::new(
+                context.screen_size.x - context.rel.height(0.01),
+                context.rel.height(0.02),
+            );
+            fps_text.draw(context);
         }
 
         context.sdl_renderer.present();
     }
 }
 
+/// Draws the visible views, bottom view first
+pub fn draw_views(context: &mut Context, views: &ViewStack) {
+    for view in views.get_visible().iter().rev() {
+        let view_data = view.get_view_data();
+
+        for graphic_object in view_data.graphic_objects.iter() {
+            graphic_object.draw(context);
+        }
+    }
+}
+
 pub struct RelativeCoordinator {
     screen_size: Vector2,
 }
@@ -145,7 +192,10 @@ impl Sprite {
 
     pub fn from_file(context: &Context, path: &str) -> Sprite {
         match context.sdl_renderer.load_texture(Path::new(path)).ok() {
-            Some(texture) => Sprite::new(texture, None, None),
+            Some(texture) => {
+                debug!("Loaded sprite {}", path);
+                Sprite::new(texture, None, None)
+            },
             None => panic!("Could not load sprite {}!", path),
         }
     }
@@ -187,10 +237,62 @@ pub struct Font {
     pub size: u16,
 }
 
+/// What a Font handle was loaded as, picked by its file extension
+enum FontFace {
+    Ttf(SdlFont),
+    Bitmap(BitmapFont),
+}
+
+impl FontFace {
+    fn size_of(&self, text: &str) -> Option<Vector2> {
+        match *self {
+            FontFace::Ttf(ref sdl_font) => sdl_font.size_of(text).ok()
+                .map(|(width, height)| Vector2::new(width as f32, height as f32)),
+            FontFace::Bitmap(ref bitmap_font) => Some(bitmap_font.size_of(text)),
+        }
+    }
+
+    /// How much a sprite made from this face's surfaces has to be scaled up
+    fn get_scale(&self) -> f32 {
+        match *self {
+            FontFace::Ttf(_) => 1.0,
+            FontFace::Bitmap(ref bitmap_font) => bitmap_font.get_scale(),
+        }
+    }
+
+    /// Renders one line.  Bitmap glyphs come out white, at native size, and
+    /// make_sprite tints and scales them.
+    fn render_line(&self, text: &str, color: Color) -> Option<Surface<'static>> {
+        match *self {
+            FontFace::Ttf(ref sdl_font) => sdl_font.render(text).blended(color).ok(),
+            FontFace::Bitmap(ref bitmap_font) => bitmap_font.render_line(text),
+        }
+    }
+
+    fn make_sprite(&self, context: &Context, surface: &Surface, color: Color) -> Option<Sprite> {
+        context.sdl_renderer.create_texture_from_surface(surface).ok().map(|mut texture| {
+            if let FontFace::Bitmap(_) = *self {
+                let (r, g, b) = color.rgb();
+                texture.set_color_mod(r, g, b);
+            }
+
+            let mut sprite = Sprite::new(texture, None, None);
+            sprite.transform.scale = Vector2::new(self.get_scale(), self.get_scale());
+            sprite
+        })
+    }
+}
+
 pub struct FontRenderer {
     sdl_ttf_context: Sdl2TtfContext,
 
-    cached_fonts: HashMap<Font, SdlFont>,
+    cached_fonts: HashMap<Font, FontFace>,
+    atlases: HashMap<Font, GlyphAtlas>,
+
+    /// For [b] in markup
+    bold_fonts: HashMap<Font, Font>,
+    /// For [icon=name] in markup
+    icons: HashMap<&'static str, Surface<'static>>,
 }
 
 impl FontRenderer {
@@ -199,9 +301,15 @@ impl FontRenderer {
             sdl_ttf_context: sdl_ttf_context,
 
             cached_fonts: HashMap::new(),
+            atlases: HashMap::new(),
+
+            bold_fonts: HashMap::new(),
+            icons: HashMap::new(),
         }
     }
 
+    /// Loads a TrueType font, or a BMFont bitmap font if the path ends in
+    /// .fnt.  Bitmap fonts are scaled by the whole number closest to font_size.
     pub fn load_font(&mut self, font_path: &'static str, font_size: u16) -> Font{
         let font = Font {
             path: font_path,
@@ -212,25 +320,44 @@ impl FontRenderer {
             return font;
         }
 
-        match self.sdl_ttf_context.load_font(Path::new(font.path), font.size).ok()
-        {
-            Some(sdl_font) => {
-                self.cached_fonts.insert(font.clone(), sdl_font);
+        let face = if font.path.ends_with(".fnt") {
+            BitmapFont::load(Path::new(font.path), font.size).map(FontFace::Bitmap)
+        } else {
+            self.sdl_ttf_context.load_font(Path::new(font.path), font.size).map(FontFace::Ttf).map_err(|error| error.to_string())
+        };
+
+        match face {
+            Ok(face) => {
+                debug!("Loaded font {}, {}", font.path, font.size);
+                self.cached_fonts.insert(font.clone(), face);
                 font
             },
-            None => {
-                panic!("Could not load font {}, {}!", font.path, font.size);
+            Err(error) => {
+                panic!("Could not load font {}, {}: {}", font.path, font.size, error);
             }
         }
     }
 
+    /// Returns the size text would be rendered at, without rendering it
+    pub fn size_of(&self, font: &Font, text: &str) -> Option<Vector2> {
+        self.cached_fonts.get(font).and_then(|face| face.size_of(text))
+    }
+
     pub fn render_sprite(&self, context: &Context, font: &Font, text: &str, color: Color) -> Option<Sprite> {
-        if let Some(sdl_font) = self.cached_fonts.get(&font) {
-            return sdl_font.render(text).blended(color).ok()
-                .and_then(|surface| context.sdl_renderer.create_texture_from_surface(&surface).ok())
-                .map(|texture| Sprite::new(texture, None, None));
+        if let Some(face) = self.cached_fonts.get(&font) {
+            return face.render_line(text, color)
+                .and_then(|surface| face.make_sprite(context, &surface, color));
         } else {
             None
         }
     }
 }
+
+pub use self::glyphs::Text;
+pub use self::markup::escape_markup;
+pub use self::text::{Align, TextStyle};
+
+mod bitmap_font;
+mod glyphs;
+mod markup;
+mod text;
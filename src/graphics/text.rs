@@ -0,0 +1,306 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect as SdlRectangle;
+use sdl2::render::BlendMode;
+use sdl2::surface::Surface;
+
+use context::Context;
+use graphics::{Font, FontRenderer, Sprite};
+use math::Vector2;
+
+/// Plain ASCII, since not every font has a real ellipsis
+const ELLIPSIS: &'static str = "...";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// How to lay out a block of text
+#[derive(Copy, Clone, Debug)]
+pub struct TextStyle {
+    pub align: Align,
+    /// Multiplies the font's line height
+    pub line_spacing: f32,
+    /// Lines wrap at this width, or only at newlines if None
+    pub max_width: Option<f32>,
+    /// Lines after this are dropped and the last one ends with an ellipsis
+    pub max_lines: Option<usize>,
+}
+
+impl TextStyle {
+    pub fn new(align: Align) -> TextStyle {
+        TextStyle {
+            align: align,
+            line_spacing: 1.0,
+            max_width: None,
+            max_lines: None,
+        }
+    }
+}
+
+/// One line of laid out text
+#[derive(Clone, Debug)]
+pub struct TextLine {
+    pub text: String,
+    /// The top left of the line, relative to the top left of the block
+    pub offset: Vector2,
+    pub size: Vector2,
+}
+
+#[derive(Clone, Debug)]
+pub struct TextLayout {
+    pub lines: Vec<TextLine>,
+    pub size: Vector2,
+}
+
+impl FontRenderer {
+    /// Breaks text into lines and works out where each one goes
+    pub fn layout_text(&self, font: &Font, text: &str, style: &TextStyle) -> TextLayout {
+        let measure = |text: &str| self.size_of(font, text).unwrap_or(Vector2::zero());
+        let line_height = measure("Ay").y;
+
+        let lines = break_lines(text, style, &measure);
+
+        let sizes: Vec<Vector2> = lines.iter().map(|line| Vector2::new(measure(line).x, line_height)).collect();
+
+        let width = match style.max_width {
+            Some(max_width) => max_width,
+            None => sizes.iter().fold(0.0, |width, size| if size.x > width { size.x } else { width }),
+        };
+        let advance = (line_height * style.line_spacing).round();
+
+        let lines: Vec<TextLine> = lines.into_iter().zip(sizes.into_iter()).enumerate().map(|(index, (line, size))| {
+            let x = match style.align {
+                Align::Left => 0.0,
+                Align::Center => ((width - size.x) / 2.0).round(),
+                Align::Right => width - size.x,
+            };
+
+            TextLine {
+                text: line,
+                offset: Vector2::new(x, index as f32 * advance),
+                size: size,
+            }
+        }).collect();
+
+        let height = if lines.is_empty() {
+            0.0
+        } else {
+            (lines.len() - 1) as f32 * advance + line_height
+        };
+
+        TextLayout {
+            lines: lines,
+            size: Vector2::new(width, height),
+        }
+    }
+
+    /// Renders a laid out block of text into one sprite
+    pub fn render_text(&self, context: &Context, font: &Font, text: &str, color: Color, style: &TextStyle) -> Option<Sprite> {
+        let face = match self.cached_fonts.get(font) {
+            Some(face) => face,
+            None => return None,
+        };
+
+        let layout = self.layout_text(font, text, style);
+        if layout.size.x < 1.0 || layout.size.y < 1.0 {
+            return None;
+        }
+
+        // Bitmap fonts are composed at their native size and scaled as a sprite
+        let scale = face.get_scale();
+
+        let mut surface = match Surface::new((layout.size.x / scale).ceil() as u32, (layout.size.y / scale).ceil() as u32, PixelFormatEnum::RGBA8888) {
+            Ok(surface) => surface,
+            Err(error) => {
+                warn!("Could not create text surface: {}", error);
+                return None;
+            },
+        };
+
+        for line in layout.lines.iter().filter(|line| !line.text.is_empty()) {
+            let mut line_surface = match face.render_line(&line.text, color) {
+                Some(line_surface) => line_surface,
+                None => continue,
+            };
+
+            // Lines don't overlap, so copy them over the transparent background as they are
+            line_surface.set_blend_mode(BlendMode::None).ok();
+
+            let destination = SdlRectangle::new(
+                (line.offset.x / scale) as i32, (line.offset.y / scale) as i32,
+                line_surface.width(), line_surface.height(),
+            ).ok().and_then(|rect| rect);
+
+            line_surface.blit(None, &mut surface, destination).ok();
+        }
+
+        face.make_sprite(context, &surface, color)
+    }
+}
+
+/// Splits text into lines at newlines, wraps them to the style's width and
+/// drops any past its number of lines
+fn break_lines<F: Fn(&str) -> Vector2>(text: &str, style: &TextStyle, measure: &F) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut truncated = false;
+
+    for paragraph in text.split('\n') {
+        match style.max_width {
+            Some(max_width) => lines.extend(wrap(paragraph, max_width, measure)),
+            None => lines.push(paragraph.to_string()),
+        }
+    }
+
+    if let Some(max_lines) = style.max_lines {
+        if lines.len() > max_lines {
+            lines.truncate(max_lines);
+            truncated = true;
+        }
+    }
+
+    if let Some(max_width) = style.max_width {
+        // A line can still be too wide if a single character doesn't fit
+        let last = lines.len().saturating_sub(1);
+        for (index, line) in lines.iter_mut().enumerate() {
+            if (truncated && index == last) || measure(line).x > max_width {
+                *line = ellipsize(line, max_width, measure);
+            }
+        }
+    } else if truncated {
+        if let Some(line) = lines.last_mut() {
+            line.push_str(ELLIPSIS);
+        }
+    }
+
+    lines
+}
+
+/// Greedily fits words onto lines, breaking words that are wider than a line
+fn wrap<F: Fn(&str) -> Vector2>(paragraph: &str, max_width: f32, measure: &F) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in paragraph.split(' ') {
+        let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+
+        if measure(&candidate).x <= max_width {
+            line = candidate;
+            continue;
+        }
+
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        line = String::new();
+
+        for c in word.chars() {
+            line.push(c);
+
+            if measure(&line).x > max_width && line.chars().count() > 1 {
+                line.pop();
+                lines.push(line);
+                line = c.to_string();
+            }
+        }
+    }
+
+    lines.push(line);
+    lines
+}
+
+/// Cuts a line down until it fits with an ellipsis on the end
+fn ellipsize<F: Fn(&str) -> Vector2>(line: &str, max_width: f32, measure: &F) -> String {
+    let mut line = line.trim_right().to_string();
+
+    loop {
+        let candidate = format!("{}{}", line, ELLIPSIS);
+        if line.is_empty() || measure(&candidate).x <= max_width {
+            return candidate;
+        }
+
+        line.pop();
+        line = line.trim_right().to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector2;
+    use super::{break_lines, ellipsize, wrap, Align, TextStyle};
+
+    /// Every character is 10 pixels wide
+    fn measure(text: &str) -> Vector2 {
+        Vector2::new(text.chars().count() as f32 * 10.0, 20.0)
+    }
+
+    fn style(max_width: Option<f32>, max_lines: Option<usize>) -> TextStyle {
+        TextStyle {
+            max_width: max_width,
+            max_lines: max_lines,
+            ..TextStyle::new(Align::Left)
+        }
+    }
+
+    #[test]
+    fn wrap_fits_words_onto_lines() {
+        assert_eq!(wrap("the quick brown fox", 90.0, &measure), vec!["the quick", "brown fox"]);
+        assert_eq!(wrap("the quick brown", 60.0, &measure), vec!["the", "quick", "brown"]);
+    }
+
+    #[test]
+    fn wrap_breaks_words_longer_than_a_line() {
+        assert_eq!(wrap("abcdefghij", 40.0, &measure), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("a abcdefgh", 40.0, &measure), vec!["a", "abcd", "efgh"]);
+    }
+
+    #[test]
+    fn empty_text_is_one_empty_line() {
+        assert_eq!(wrap("", 40.0, &measure), vec![""]);
+        assert_eq!(break_lines("", &style(None, None), &measure), vec![""]);
+        assert_eq!(break_lines("", &style(Some(40.0), Some(1)), &measure), vec![""]);
+    }
+
+    #[test]
+    fn newlines_always_break() {
+        assert_eq!(break_lines("one\ntwo three", &style(None, None), &measure), vec!["one", "two three"]);
+        assert_eq!(break_lines("one\ntwo three", &style(Some(50.0), None), &measure), vec!["one", "two", "three"]);
+        assert_eq!(break_lines("one\n\ntwo", &style(None, None), &measure), vec!["one", "", "two"]);
+    }
+
+    #[test]
+    fn lines_past_the_limit_are_dropped_with_an_ellipsis() {
+        assert_eq!(break_lines("one two three four", &style(Some(90.0), Some(2)), &measure), vec!["one two", "three..."]);
+        assert_eq!(break_lines("one\ntwo\nthree", &style(None, Some(2)), &measure), vec!["one", "two..."]);
+        assert_eq!(break_lines("one\ntwo", &style(None, Some(2)), &measure), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn ellipsize_cuts_until_it_fits() {
+        assert_eq!(ellipsize("abcdefgh", 60.0, &measure), "abc...");
+        // Spaces left before the ellipsis are trimmed
+        assert_eq!(ellipsize("ab cdef", 60.0, &measure), "ab...");
+        assert_eq!(ellipsize("abcdefgh", 10.0, &measure), "...");
+    }
+}
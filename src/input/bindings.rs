@@ -0,0 +1,205 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use sdl2::controller::Button as ControllerButton;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::Mouse;
+
+use config::Config;
+use input::{Action, ACTIONS};
+
+/// A physical key or button that can trigger an action
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Binding {
+    Key(Keycode),
+    /// SDL's mouse button number: 1 is left, 2 middle, 3 right
+    Mouse(u8),
+    Controller(ControllerButton),
+}
+
+impl Binding {
+    pub fn from_mouse(mouse: Mouse) -> Option<Binding> {
+        match mouse {
+            Mouse::Left => Some(Binding::Mouse(1)),
+            Mouse::Middle => Some(Binding::Mouse(2)),
+            Mouse::Right => Some(Binding::Mouse(3)),
+            Mouse::X1 => Some(Binding::Mouse(4)),
+            Mouse::X2 => Some(Binding::Mouse(5)),
+            _ => None,
+        }
+    }
+
+    /// Parses the form written by to_string, e.g. `key:Return`, `mouse:1` or `button:a`
+    pub fn from_str(s: &str) -> Option<Binding> {
+        let s = s.trim();
+
+        if s.starts_with("key:") {
+            Keycode::from_name(&s[4..]).map(Binding::Key)
+        } else if s.starts_with("mouse:") {
+            s[6..].parse().ok().map(Binding::Mouse)
+        } else if s.starts_with("button:") {
+            ControllerButton::from_string(&s[7..]).map(Binding::Controller)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        match *self {
+            Binding::Key(keycode) => format!("key:{}", keycode.name()),
+            Binding::Mouse(button) => format!("mouse:{}", button),
+            Binding::Controller(button) => format!("button:{}", button.string()),
+        }
+    }
+
+    /// Returns a name to show the player
+    pub fn describe(&self) -> String {
+        match *self {
+            Binding::Key(keycode) => keycode.name(),
+            Binding::Mouse(1) => "Left Click".to_string(),
+            Binding::Mouse(2) => "Middle Click".to_string(),
+            Binding::Mouse(3) => "Right Click".to_string(),
+            Binding::Mouse(button) => format!("Mouse {}", button),
+            Binding::Controller(button) => format!("Pad {}", button.string().to_uppercase()),
+        }
+    }
+}
+
+/// Maps keys and buttons to actions.  An action can have any number of
+/// bindings, but each binding triggers only one action.
+#[derive(Clone)]
+pub struct Bindings {
+    bindings: Vec<(Binding, Action)>,
+}
+
+impl Bindings {
+    pub fn default() -> Bindings {
+        let mut bindings = Bindings {
+            bindings: Vec::new(),
+        };
+
+        for action in ACTIONS.iter() {
+            for binding in default_bindings(*action) {
+                bindings.bind(*action, binding);
+            }
+        }
+
+        bindings
+    }
+
+    /// Reads each action's bindings from its `bind_<action>` config key.
+    /// Actions without a key keep their defaults.
+    pub fn load(config: &Config) -> Bindings {
+        let mut bindings = Bindings::default();
+
+        for action in ACTIONS.iter() {
+            let value = match config.get(&config_key(*action)) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            bindings.clear(*action);
+            for item in split_list(value) {
+                match Binding::from_str(&item) {
+                    Some(binding) => { bindings.bind(*action, binding); },
+                    None => warn!("Ignoring unknown binding {} for {:?}", item, action),
+                }
+            }
+        }
+
+        bindings
+    }
+
+    pub fn save(&self, config: &mut Config) {
+        for action in ACTIONS.iter() {
+            let value = self.get(*action).iter()
+                .map(|binding| binding.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+
+            config.set(&config_key(*action), value);
+        }
+    }
+
+    pub fn action_for(&self, binding: Binding) -> Option<Action> {
+        self.bindings.iter().find(|&&(b, _)| b == binding).map(|&(_, action)| action)
+    }
+
+    pub fn get(&self, action: Action) -> Vec<Binding> {
+        self.bindings.iter().filter(|&&(_, a)| a == action).map(|&(binding, _)| binding).collect()
+    }
+
+    /// Binds to action, taking the binding away from any other action.
+    /// Returns the action it was taken from.
+    pub fn bind(&mut self, action: Action, binding: Binding) -> Option<Action> {
+        let previous = self.action_for(binding);
+        self.bindings.retain(|&(b, _)| b != binding);
+        self.bindings.push((binding, action));
+
+        match previous {
+            Some(previous) if previous != action => Some(previous),
+            _ => None,
+        }
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.bindings.retain(|&(_, a)| a != action);
+    }
+}
+
+fn config_key(action: Action) -> String {
+    format!("bind_{}", action.name().to_lowercase())
+}
+
+fn default_bindings(action: Action) -> Vec<Binding> {
+    match action {
+        Action::Up => vec![Binding::Key(Keycode::W), Binding::Key(Keycode::Up), Binding::Controller(ControllerButton::DPadUp)],
+        Action::Down => vec![Binding::Key(Keycode::S), Binding::Key(Keycode::Down), Binding::Controller(ControllerButton::DPadDown)],
+        Action::Left => vec![Binding::Key(Keycode::A), Binding::Key(Keycode::Left), Binding::Controller(ControllerButton::DPadLeft)],
+        Action::Right => vec![Binding::Key(Keycode::D), Binding::Key(Keycode::Right), Binding::Controller(ControllerButton::DPadRight)],
+        Action::Enter => vec![Binding::Key(Keycode::Return), Binding::Mouse(1), Binding::Controller(ControllerButton::A)],
+        Action::Pause => vec![Binding::Key(Keycode::Escape), Binding::Controller(ControllerButton::Start), Binding::Controller(ControllerButton::B)],
+    }
+}
+
+/// Splits a comma separated list of bindings.  Key names can themselves be a
+/// comma, so a piece only starts a new binding if it has a known prefix.
+fn split_list(value: &str) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+
+    for piece in value.split(',') {
+        let trimmed = piece.trim_left();
+        let starts_binding = trimmed.starts_with("key:") || trimmed.starts_with("mouse:") || trimmed.starts_with("button:");
+
+        match items.last_mut() {
+            Some(last) if !starts_binding => {
+                last.push(',');
+                last.push_str(piece);
+                continue;
+            },
+            _ => {},
+        }
+
+        if starts_binding {
+            items.push(trimmed.to_string());
+        }
+    }
+
+    items
+}
@@ -0,0 +1,177 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use sdl2::GameControllerSubsystem;
+use sdl2::controller::{Axis, GameController};
+
+use input::{Action, Input};
+use math::Vector2;
+
+/// Once a stick is pointing in a direction, it has to fall this far under the
+/// deadzone before the direction is released, so it doesn't flutter at the edge.
+const RELEASE_FACTOR: f32 = 0.7;
+
+/// How much stronger the other axis has to be before the direction switches to it
+const SWITCH_FACTOR: f32 = 1.3;
+
+struct Controller {
+    sdl_controller: GameController,
+    instance_id: i32,
+
+    stick: Vector2,
+    direction: Option<Action>,
+}
+
+/// Keeps track of connected game controllers and turns their left sticks
+/// into directions.  Buttons go through the bindings like keys do.
+pub struct Controllers {
+    sdl_game_controller: Option<GameControllerSubsystem>,
+    controllers: Vec<Controller>,
+
+    deadzone: f32,
+}
+
+impl Controllers {
+    /// Opens every controller that's already plugged in.  deadzone is the
+    /// fraction of a stick's range that's ignored.
+    pub fn new(sdl_game_controller: Option<GameControllerSubsystem>, deadzone: f32) -> Controllers {
+        let mut controllers = Controllers {
+            sdl_game_controller: sdl_game_controller,
+            controllers: Vec::new(),
+
+            deadzone: deadzone,
+        };
+
+        let count = match controllers.sdl_game_controller {
+            Some(ref subsystem) => subsystem.num_joysticks().unwrap_or(0),
+            None => 0,
+        };
+
+        for index in 0..count {
+            controllers.add(index);
+        }
+
+        controllers
+    }
+
+    pub fn count(&self) -> usize {
+        self.controllers.len()
+    }
+
+    /// Opens the controller at device index, if it is one and isn't open already
+    pub fn add(&mut self, device_index: u32) {
+        let subsystem = match self.sdl_game_controller {
+            Some(ref subsystem) => subsystem,
+            None => return,
+        };
+
+        if !subsystem.is_game_controller(device_index) {
+            return;
+        }
+
+        match subsystem.open(device_index) {
+            Ok(sdl_controller) => {
+                let instance_id = sdl_controller.instance_id();
+                if self.controllers.iter().any(|controller| controller.instance_id == instance_id) {
+                    return;
+                }
+
+                info!("Controller connected: {}", sdl_controller.name());
+                self.controllers.push(Controller {
+                    sdl_controller: sdl_controller,
+                    instance_id: instance_id,
+
+                    stick: Vector2::zero(),
+                    direction: None,
+                });
+            },
+            Err(error) => warn!("Could not open controller {}: {:?}", device_index, error),
+        }
+    }
+
+    /// Closes a controller, releasing any direction it was holding
+    pub fn remove(&mut self, instance_id: i32) -> Vec<Input> {
+        let mut released = Vec::new();
+
+        if let Some(index) = self.controllers.iter().position(|controller| controller.instance_id == instance_id) {
+            let controller = self.controllers.remove(index);
+            info!("Controller disconnected: {}", controller.sdl_controller.name());
+
+            if let Some(direction) = controller.direction {
+                released.push(Input::Release(direction));
+            }
+        }
+
+        released
+    }
+
+    /// Handles a controller axis moving.  value is SDL's raw -32768 to 32767.
+    pub fn axis_motion(&mut self, instance_id: i32, axis: Axis, value: i16) -> Vec<Input> {
+        let deadzone = self.deadzone;
+
+        let controller = match self.controllers.iter_mut().find(|controller| controller.instance_id == instance_id) {
+            Some(controller) => controller,
+            None => return Vec::new(),
+        };
+
+        let value = if value < 0 { value as f32 / 32768.0 } else { value as f32 / 32767.0 };
+        match axis {
+            Axis::LeftX => controller.stick.x = value,
+            Axis::LeftY => controller.stick.y = value,
+            _ => return Vec::new(),
+        }
+
+        let mut inputs = vec![Input::JoystickMotion(controller.stick)];
+
+        let direction = stick_direction(controller.stick, controller.direction, deadzone);
+        if direction != controller.direction {
+            if let Some(old) = controller.direction {
+                inputs.push(Input::Release(old));
+            }
+            if let Some(new) = direction {
+                inputs.push(new.to_input());
+            }
+            controller.direction = direction;
+        }
+
+        inputs
+    }
+}
+
+/// Picks the direction a stick is pointing, preferring the current one
+fn stick_direction(stick: Vector2, current: Option<Action>, deadzone: f32) -> Option<Action> {
+    let (x, y) = (stick.x.abs(), stick.y.abs());
+
+    let threshold = if current.is_some() { deadzone * RELEASE_FACTOR } else { deadzone };
+    if x < threshold && y < threshold {
+        return None;
+    }
+
+    let horizontal = match current {
+        Some(Action::Left) | Some(Action::Right) => y <= x * SWITCH_FACTOR,
+        Some(Action::Up) | Some(Action::Down) => x > y * SWITCH_FACTOR,
+        _ => x > y,
+    };
+
+    if horizontal {
+        Some(if stick.x < 0.0 { Action::Left } else { Action::Right })
+    } else {
+        Some(if stick.y < 0.0 { Action::Up } else { Action::Down })
+    }
+}
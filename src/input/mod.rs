@@ -0,0 +1,457 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use sdl2::event::{Event, WindowEventId};
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::Mouse;
+
+use math::Vector2;
+
+#[derive(Clone, PartialEq)]
+pub enum Input {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    MouseMotion(Vector2),
+    JoystickMotion(Vector2),
+    Pause,
+    Exit,
+
+    /// The window stopped being the one the keyboard goes to
+    FocusLost,
+
+    /// The action's key or button was let go
+    Release(Action),
+
+    /// The key or button pressed while capturing a binding
+    Bind(Binding),
+
+    /// The left mouse button or a finger went down or up at a screen position
+    PointerDown(Vector2),
+    PointerUp(Vector2),
+
+    // Only sent in text mode, where Left and Right move the caret and
+    // Pause cancels
+    Text(String),
+    Backspace,
+    Delete,
+    Home,
+    End,
+    Commit,
+
+    // Game clock controls for hunting bugs
+    DebugTogglePause,
+    DebugStep,
+    DebugSlower,
+    DebugFaster,
+}
+
+impl Input {
+    /// Returns the action this input presses, if it's one whose held state is tracked
+    pub fn action(&self) -> Option<Action> {
+        match *self {
+            Input::Up => Some(Action::Up),
+            Input::Down => Some(Action::Down),
+            Input::Left => Some(Action::Left),
+            Input::Right => Some(Action::Right),
+            Input::Enter => Some(Action::Enter),
+            Input::Pause => Some(Action::Pause),
+            _ => None,
+        }
+    }
+}
+
+/// The inputs that can be held down
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Pause,
+}
+
+pub const ACTION_COUNT: usize = 6;
+
+pub const ACTIONS: [Action; ACTION_COUNT] = [
+    Action::Up,
+    Action::Down,
+    Action::Left,
+    Action::Right,
+    Action::Enter,
+    Action::Pause,
+];
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Action::Up => "Up",
+            Action::Down => "Down",
+            Action::Left => "Left",
+            Action::Right => "Right",
+            Action::Enter => "Enter",
+            Action::Pause => "Pause",
+        }
+    }
+
+    pub fn to_input(&self) -> Input {
+        match *self {
+            Action::Up => Input::Up,
+            Action::Down => Input::Down,
+            Action::Left => Input::Left,
+            Action::Right => Input::Right,
+            Action::Enter => Input::Enter,
+            Action::Pause => Input::Pause,
+        }
+    }
+}
+
+/// The mouse id SDL gives to mouse events it synthesizes from touches
+const TOUCH_MOUSE_ID: u32 = 0xffffffff;
+
+/// How far, relative to the screen height, a drag has to go to count as a swipe
+const SWIPE_DISTANCE: f32 = 0.04;
+
+/// Turns SDL events into inputs according to the current bindings
+pub struct InputTranslator {
+    bindings: Bindings,
+    controllers: Controllers,
+
+    /// For turning normalized touch positions into screen positions
+    screen_size: Vector2,
+
+    /// Where the current click or touch went down, to tell swipes from taps
+    pointer_start: Option<Vector2>,
+
+    /// Whether the next key or button press should be reported as an Input::Bind
+    capturing: bool,
+
+    /// Whether the keyboard is typing text instead of triggering actions
+    text_mode: bool,
+}
+
+impl InputTranslator {
+    pub fn new(bindings: Bindings, controllers: Controllers, screen_size: Vector2) -> InputTranslator {
+        InputTranslator {
+            bindings: bindings,
+            controllers: controllers,
+
+            screen_size: screen_size,
+
+            pointer_start: None,
+
+            capturing: false,
+
+            text_mode: false,
+        }
+    }
+
+    pub fn get_bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    pub fn get_bindings_mut(&mut self) -> &mut Bindings {
+        &mut self.bindings
+    }
+
+    /// Reports the next key or button pressed as an Input::Bind instead of an
+    /// action, for the player to choose a binding.  Escape cancels with an
+    /// Input::Pause.
+    pub fn capture_binding(&mut self) {
+        self.capturing = true;
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capturing
+    }
+
+    /// Switches the keyboard between typing and actions.  Use
+    /// Context::start_text_input, which also tells SDL.
+    pub fn set_text_mode(&mut self, text_mode: bool) {
+        self.text_mode = text_mode;
+    }
+
+    pub fn is_text_mode(&self) -> bool {
+        self.text_mode
+    }
+
+    pub fn set_screen_size(&mut self, screen_size: Vector2) {
+        self.screen_size = screen_size;
+    }
+
+    pub fn translate(&mut self, event: &Event) -> Vec<Input> {
+        if self.text_mode {
+            match *event {
+                Event::TextInput { ref text, .. } => return vec![Input::Text(text.clone())],
+                // Editing keys repeat while held
+                Event::KeyDown { keycode: Some(Keycode::Backspace), .. } => return vec![Input::Backspace],
+                Event::KeyDown { keycode: Some(Keycode::Delete), .. } => return vec![Input::Delete],
+                Event::KeyDown { keycode: Some(Keycode::Home), repeat: false, .. } => return vec![Input::Home],
+                Event::KeyDown { keycode: Some(Keycode::End), repeat: false, .. } => return vec![Input::End],
+                Event::KeyDown { keycode: Some(Keycode::Left), .. } => return vec![Input::Left],
+                Event::KeyDown { keycode: Some(Keycode::Right), .. } => return vec![Input::Right],
+                Event::KeyDown { keycode: Some(Keycode::Return), repeat: false, .. } => return vec![Input::Commit],
+                Event::KeyDown { keycode: Some(Keycode::Escape), repeat: false, .. } => return vec![Input::Pause],
+                Event::KeyDown { .. } => return Vec::new(),
+                // Key ups still release actions as usual, so nothing is left held
+                _ => {},
+            }
+        }
+
+        let (binding, down) = match *event {
+            Event::MouseMotion { x, y, .. } => {
+                return vec![Input::MouseMotion(Vector2::new(x as f32, y as f32))];
+            },
+            Event::Quit {..} => return vec![Input::Exit],
+            Event::Window { win_event_id: WindowEventId::FocusLost, .. } => return vec![Input::FocusLost],
+
+            // Held keys are tracked by InputState, which does its own repeating
+            Event::KeyDown { repeat: true, .. } => return Vec::new(),
+
+            Event::KeyDown { keycode: Some(Keycode::F5), .. } => return vec![Input::DebugTogglePause],
+            Event::KeyDown { keycode: Some(Keycode::F6), .. } => return vec![Input::DebugStep],
+            Event::KeyDown { keycode: Some(Keycode::F7), .. } => return vec![Input::DebugSlower],
+            Event::KeyDown { keycode: Some(Keycode::F8), .. } => return vec![Input::DebugFaster],
+
+            Event::FingerDown { x, y, .. } => {
+                let position = Vector2::new(x, y) * self.screen_size;
+                return vec![self.pointer_down(position)];
+            },
+            Event::FingerUp { x, y, .. } => {
+                let position = Vector2::new(x, y) * self.screen_size;
+                return self.pointer_up(position);
+            },
+
+            Event::ControllerDeviceAdded { which, .. } => {
+                self.controllers.add(which as u32);
+                return Vec::new();
+            },
+            Event::ControllerDeviceRemoved { which, .. } => {
+                return self.controllers.remove(which);
+            },
+            Event::ControllerAxisMotion { which, axis, value, .. } => {
+                // Sticks only steer while not choosing a binding
+                let inputs = self.controllers.axis_motion(which, axis, value);
+                return if self.capturing { Vec::new() } else { inputs };
+            },
+
+            Event::KeyDown { keycode: Some(keycode), .. } => (Binding::Key(keycode), true),
+            Event::KeyUp { keycode: Some(keycode), .. } => (Binding::Key(keycode), false),
+            Event::MouseButtonDown { mouse_btn, .. } => match Binding::from_mouse(mouse_btn) {
+                Some(binding) => (binding, true),
+                None => return Vec::new(),
+            },
+            Event::MouseButtonUp { mouse_btn, .. } => match Binding::from_mouse(mouse_btn) {
+                Some(binding) => (binding, false),
+                None => return Vec::new(),
+            },
+            Event::ControllerButtonDown { button, .. } => (Binding::Controller(button), true),
+            Event::ControllerButtonUp { button, .. } => (Binding::Controller(button), false),
+            _ => return Vec::new(),
+        };
+
+        if self.capturing {
+            // Still let go of actions, like the press that started the capture
+            if !down {
+                return match self.bindings.action_for(binding) {
+                    Some(action) => vec![Input::Release(action)],
+                    None => Vec::new(),
+                };
+            }
+
+            self.capturing = false;
+            if binding == Binding::Key(Keycode::Escape) {
+                return vec![Input::Pause];
+            }
+            return vec![Input::Bind(binding)];
+        }
+
+        // SDL also reports touches as mouse clicks, which still work the
+        // menus, but their pointer inputs come from the finger events instead.
+        // The pointer goes first, so a click focuses what's under it before
+        // the bound action presses it.
+        let mut inputs = match *event {
+            Event::MouseButtonDown { mouse_btn: Mouse::Left, which, x, y, .. } if which != TOUCH_MOUSE_ID => {
+                vec![self.pointer_down(Vector2::new(x as f32, y as f32))]
+            },
+            Event::MouseButtonUp { mouse_btn: Mouse::Left, which, x, y, .. } if which != TOUCH_MOUSE_ID => {
+                self.pointer_up(Vector2::new(x as f32, y as f32))
+            },
+            _ => Vec::new(),
+        };
+
+        if let Some(action) = self.bindings.action_for(binding) {
+            inputs.push(if down { action.to_input() } else { Input::Release(action) });
+        }
+
+        inputs
+    }
+
+    fn pointer_down(&mut self, position: Vector2) -> Input {
+        self.pointer_start = Some(position);
+        Input::PointerDown(position)
+    }
+
+    /// A release far enough from where the pointer went down is a swipe, and
+    /// comes through as a press and release of the way it went instead
+    fn pointer_up(&mut self, position: Vector2) -> Vec<Input> {
+        let drag = match self.pointer_start.take() {
+            Some(start) => position - start,
+            None => return vec![Input::PointerUp(position)],
+        };
+
+        if drag.x.abs().max(drag.y.abs()) < self.screen_size.y * SWIPE_DISTANCE {
+            return vec![Input::PointerUp(position)];
+        }
+
+        let action = if drag.x.abs() > drag.y.abs() {
+            if drag.x > 0.0 { Action::Right } else { Action::Left }
+        } else {
+            if drag.y > 0.0 { Action::Down } else { Action::Up }
+        };
+
+        vec![action.to_input(), Input::Release(action)]
+    }
+}
+
+#[derive(Copy, Clone)]
+struct ActionState {
+    held: bool,
+    pressed: bool,
+    released: bool,
+    repeated: bool,
+
+    held_ns: i64,
+    next_repeat_ns: i64,
+}
+
+impl ActionState {
+    fn new() -> ActionState {
+        ActionState {
+            held: false,
+            pressed: false,
+            released: false,
+            repeated: false,
+
+            held_ns: 0,
+            next_repeat_ns: 0,
+        }
+    }
+}
+
+/// Tracks which actions are pressed, held and released, updated once per
+/// logic tick from that tick's input.
+pub struct InputState {
+    actions: [ActionState; ACTION_COUNT],
+
+    repeat_delay_ns: i64,
+    repeat_interval_ns: i64,
+}
+
+impl InputState {
+    pub fn new(repeat_delay_ns: i64, repeat_interval_ns: i64) -> InputState {
+        InputState {
+            actions: [ActionState::new(); ACTION_COUNT],
+
+            repeat_delay_ns: repeat_delay_ns,
+            repeat_interval_ns: repeat_interval_ns,
+        }
+    }
+
+    /// Applies a tick's input.  elapsed_ns should be real time, so that
+    /// repeating doesn't slow down with the game clock.
+    pub fn update(&mut self, input: &Vec<Input>, elapsed_ns: i64) {
+        for state in self.actions.iter_mut() {
+            state.pressed = false;
+            state.released = false;
+            state.repeated = false;
+
+            if state.held {
+                state.held_ns += elapsed_ns;
+
+                if state.held_ns >= state.next_repeat_ns {
+                    state.repeated = true;
+                    state.next_repeat_ns += self.repeat_interval_ns;
+                }
+            }
+        }
+
+        for i in input.iter() {
+            match *i {
+                Input::Release(action) => {
+                    let state = &mut self.actions[action as usize];
+                    if state.held || state.pressed {
+                        state.held = false;
+                        state.released = true;
+                        state.repeated = false;
+                    }
+                },
+                _ => if let Some(action) = i.action() {
+                    let state = &mut self.actions[action as usize];
+                    if !state.held {
+                        state.held = true;
+                        state.pressed = true;
+                        state.held_ns = 0;
+                        state.next_repeat_ns = self.repeat_delay_ns;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Releases everything, e.g. when the window loses focus and we won't hear about key ups
+    pub fn clear(&mut self) {
+        for state in self.actions.iter_mut() {
+            *state = ActionState::new();
+        }
+    }
+
+    /// Whether the action went down this tick
+    pub fn pressed(&self, action: Action) -> bool {
+        self.actions[action as usize].pressed
+    }
+
+    pub fn held(&self, action: Action) -> bool {
+        self.actions[action as usize].held
+    }
+
+    /// Whether the action was let go this tick
+    pub fn released(&self, action: Action) -> bool {
+        self.actions[action as usize].released
+    }
+
+    /// How long the action has been held, or was held if it was released this tick
+    pub fn held_ns(&self, action: Action) -> i64 {
+        self.actions[action as usize].held_ns
+    }
+
+    /// Whether a held action auto-repeated this tick.  Doesn't include the initial press.
+    pub fn repeated(&self, action: Action) -> bool {
+        self.actions[action as usize].repeated
+    }
+}
+
+pub use self::bindings::{Binding, Bindings};
+pub use self::controller::Controllers;
+
+mod bindings;
+mod controller;
@@ -17,16 +17,285 @@
 // Copyright 2016 Chris Foster
 //
 
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use time;
+
+use config::{self, Config};
+
 macro_rules! log {
-    ($fmt:expr) => (println!(concat!("[Log]: ", $fmt)));
-    ($fmt:expr, $($arg:tt)*) => (println!(concat!("[Log]: ", $fmt), $($arg)*));
+    ($level:expr, $($arg:tt)+) => {
+        if ::log::enabled($level, module_path!()) {
+            ::log::write($level, module_path!(), format_args!($($arg)+));
+        }
+    };
+}
+
+macro_rules! error {
+    ($($arg:tt)+) => (log!(::log::Level::Error, $($arg)+));
+}
+
+macro_rules! warn {
+    ($($arg:tt)+) => (log!(::log::Level::Warn, $($arg)+));
+}
+
+macro_rules! info {
+    ($($arg:tt)+) => (log!(::log::Level::Info, $($arg)+));
 }
 
 macro_rules! debug {
-    ($fmt:expr) => {
-        if cfg!(debug_assertions) { println!(concat!("[Debug]: ", $fmt)); }
+    ($($arg:tt)+) => (log!(::log::Level::Debug, $($arg)+));
+}
+
+macro_rules! trace {
+    ($($arg:tt)+) => (log!(::log::Level::Trace, $($arg)+));
+}
+
+const LOG_FILE: &'static str = "reaping.log";
+const MAX_LOG_FILES: u32 = 5;
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// How many of the most recent lines are kept in memory for crash reports
+const RECENT_LINES: usize = 200;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Level {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn from_str(s: &str) -> Option<Level> {
+        match &*s.trim().to_lowercase() {
+            "off" => Some(Level::Off),
+            "error" => Some(Level::Error),
+            "warn" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match *self {
+            Level::Off => "OFF",
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        })
+    }
+}
+
+/// Decides which messages get through, per module.  Parsed from strings like
+/// `info,view::game=trace`: a bare level sets the default and `module=level`
+/// overrides it for that module and everything beneath it.
+struct Filter {
+    default: Level,
+    modules: Vec<(String, Level)>,
+}
+
+impl Filter {
+    fn parse(spec: &str, default: Level) -> Filter {
+        let mut filter = Filter {
+            default: default,
+            modules: Vec::new(),
+        };
+
+        for directive in spec.split(',').map(|d| d.trim()).filter(|d| !d.is_empty()) {
+            match directive.find('=') {
+                Some(index) => {
+                    match Level::from_str(&directive[index + 1..]) {
+                        Some(level) => {
+                            let module = directive[..index].trim();
+                            let module = if module.starts_with("reaping::") || module == "reaping" {
+                                module.to_string()
+                            } else {
+                                format!("reaping::{}", module)
+                            };
+                            filter.modules.push((module, level));
+                        },
+                        None => println!("Ignoring malformed log directive: {}", directive),
+                    }
+                },
+                None => match Level::from_str(directive) {
+                    Some(level) => filter.default = level,
+                    None => println!("Ignoring malformed log directive: {}", directive),
+                },
+            }
+        }
+
+        // Most specific module first
+        filter.modules.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+        filter
+    }
+
+    fn level_for(&self, module: &str) -> Level {
+        for &(ref prefix, level) in self.modules.iter() {
+            if module == prefix || (module.starts_with(prefix.as_str()) && module[prefix.len()..].starts_with("::")) {
+                return level;
+            }
+        }
+        self.default
+    }
+}
+
+struct Logger {
+    filter: Filter,
+
+    file: Option<File>,
+    file_path: Option<PathBuf>,
+    file_size: u64,
+}
+
+thread_local!(static LOGGER: RefCell<Option<Logger>> = RefCell::new(None));
+thread_local!(static RECENT: RefCell<VecDeque<String>> = RefCell::new(VecDeque::new()));
+
+/// Starts logging to stdout and a rotating file in the user data directory.
+/// The filter is read from the `log` config key; `log_file = false` disables
+/// the file sink.
+pub fn init(config: &Config) {
+    let default = if cfg!(debug_assertions) { Level::Debug } else { Level::Info };
+    let filter = Filter::parse(config.get("log").unwrap_or(""), default);
+
+    let file_path = if config.get_or("log_file", true) {
+        config::user_data_dir().map(|dir| dir.join("logs"))
+    } else {
+        None
     };
-    ($fmt:expr, $($arg:tt)*) => {
-        if cfg!(debug_assertions) { println!(concat!("[Debug]: ", $fmt), $($arg)*); }
+
+    let mut logger = Logger {
+        filter: filter,
+
+        file: None,
+        file_path: file_path.map(|dir| dir.join(LOG_FILE)),
+        file_size: 0,
     };
+
+    if let Some(path) = logger.file_path.clone() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).ok();
+        }
+        rotate(&path);
+        logger.file = open(&path);
+    }
+
+    LOGGER.with(|cell| *cell.borrow_mut() = Some(logger));
+}
+
+/// Returns the path of the current log file, if there is one
+pub fn file_path() -> Option<PathBuf> {
+    LOGGER.with(|cell| cell.borrow().as_ref().and_then(|logger| {
+        if logger.file.is_some() { logger.file_path.clone() } else { None }
+    }))
+}
+
+/// False while the logger is in use, e.g. when a panic hook logs about a
+/// panic that happened while logging, so the message is dropped rather than
+/// panicking again
+pub fn enabled(level: Level, module: &str) -> bool {
+    LOGGER.with(|cell| match cell.try_borrow() {
+        Ok(logger) => match *logger {
+            Some(ref logger) => level != Level::Off && level <= logger.filter.level_for(module),
+            // Before init, behave like the old println! macros
+            None => level <= if cfg!(debug_assertions) { Level::Debug } else { Level::Info },
+        },
+        Err(_) => false,
+    })
+}
+
+pub fn write(level: Level, module: &str, args: fmt::Arguments) {
+    let now = time::now();
+    let line = format!(
+        "{}.{:03} [{:<5}] {}: {}",
+        time::strftime("%Y-%m-%d %H:%M:%S", &now).unwrap_or(String::new()),
+        now.tm_nsec / 1_000_000,
+        level,
+        module,
+        args
+    );
+
+    println!("{}", line);
+
+    LOGGER.with(|cell| if let Ok(mut logger) = cell.try_borrow_mut() {
+        if let Some(ref mut logger) = *logger {
+            logger.write_file(&line);
+        }
+    });
+
+    RECENT.with(|cell| if let Ok(mut recent) = cell.try_borrow_mut() {
+        if recent.len() == RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line);
+    });
+}
+
+/// Returns the most recently logged lines, oldest first.  Safe to call from a
+/// panic hook, even if the panic happened while logging.
+pub fn recent_lines() -> Vec<String> {
+    RECENT.with(|cell| match cell.try_borrow() {
+        Ok(recent) => recent.iter().cloned().collect(),
+        Err(_) => Vec::new(),
+    })
+}
+
+impl Logger {
+    fn write_file(&mut self, line: &str) {
+        let failed = match self.file {
+            Some(ref mut file) => writeln!(file, "{}", line).is_err(),
+            None => return,
+        };
+
+        if failed {
+            // Don't keep trying to write to a broken file
+            self.file = None;
+            return;
+        }
+
+        self.file_size += line.len() as u64 + 1;
+        if self.file_size > MAX_LOG_SIZE {
+            if let Some(ref path) = self.file_path {
+                self.file = None;
+                rotate(path);
+                self.file = open(path);
+            }
+            self.file_size = 0;
+        }
+    }
+}
+
+/// Shifts reaping.log to reaping.1.log, reaping.1.log to reaping.2.log, and so
+/// on, dropping the oldest.
+fn rotate(path: &PathBuf) {
+    let numbered = |n: u32| path.with_file_name(format!("reaping.{}.log", n));
+
+    fs::remove_file(numbered(MAX_LOG_FILES - 1)).ok();
+    for n in (1..MAX_LOG_FILES - 1).rev() {
+        fs::rename(numbered(n), numbered(n + 1)).ok();
+    }
+    fs::rename(path, numbered(1)).ok();
+}
+
+fn open(path: &PathBuf) -> Option<File> {
+    match OpenOptions::new().create(true).write(true).truncate(true).open(path) {
+        Ok(file) => Some(file),
+        Err(error) => {
+            println!("Could not open log file {}: {}", path.display(), error);
+            None
+        },
+    }
 }
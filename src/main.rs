@@ -17,6 +17,7 @@
 // Copyright 2016 Chris Foster
 //
 
+extern crate backtrace;
 extern crate sdl2;
 extern crate sdl2_image;
 extern crate sdl2_ttf;
@@ -25,36 +26,139 @@ extern crate time;
 #[macro_use]
 mod log;
 
+mod config;
 mod context;
+mod crash;
 mod graphics;
 mod input;
 mod math;
+mod random;
+mod replay;
+mod save;
 //mod sound;
 mod timer;
+mod ui;
 mod view;
 
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use sdl2::event::{Event, WindowEventId};
+
+use config::Config;
 use context::Context;
 use graphics::GraphicRenderer;
-use input::{Input, InputTranslator};
+use input::{Input, InputState};
+use replay::{Player, Recorder};
+use save::SaveData;
 use timer::Timer;
-use view::{MainMenuView, View, ViewAction};
+use view::{Difficulty, MainMenuView, StackEvent, ViewStack, ViewTransition};
+
+/// How many logic steps may run back to back before we give up on catching up
+const MAX_CATCH_UP_STEPS: u32 = 5;
 
 fn main() {
-    let mut context = Context::new();
+    let mut config = Config::load();
+    let unrecognized_args = config.apply_args(std::env::args().skip(1));
+
+    log::init(&config);
+    info!("The Reaping v{}", env!("CARGO_PKG_VERSION"));
+    if let Some(path) = log::file_path() {
+        info!("Logging to {}", path.display());
+    }
+
+    // Subcommands run without opening a window
+    if unrecognized_args.first().map(|arg| arg.as_str()) == Some("check-levels") {
+        let solvable = view::check_levels(&config, &unrecognized_args[1..]);
+        std::process::exit(if solvable { 0 } else { 1 });
+    }
+
+    for arg in unrecognized_args {
+        warn!("Ignoring unrecognized argument: {}", arg);
+    }
 
-    let input_translator = InputTranslator::new();
+    let crash_report = crash::take_pending_report();
+    crash::install(&config);
+
+    let mut context = Context::new(config);
+    context.crash_report = crash_report;
+
+    let mut input_state = InputState::new(
+        context.config.get_or("key_repeat_delay_ms", 400) * 1_000_000,
+        context.config.get_or("key_repeat_interval_ms", 80) * 1_000_000,
+    );
+
+    let mut graphic_renderer = GraphicRenderer::new(&mut context);
+
+    let debug_keys = context.config.get_or("debug_keys", cfg!(debug_assertions));
+
+    let mut player = match context.config.get("replay").map(PathBuf::from) {
+        Some(path) => match Player::load(&path) {
+            Ok(player) => {
+                info!("Replaying {}", path.display());
+                Some(player)
+            },
+            Err(error) => {
+                error!("Could not load replay {}: {}", path.display(), error);
+                None
+            },
+        },
+        None => None,
+    };
+
+    // A replay only plays back the same way from the same seed and settings.
+    // They're played in a copy of the config, so the player's own come back
+    // once it's finished.
+    let mut own_config = None;
+    if let Some(ref player) = player {
+        let header = player.get_header();
+        context.random.reseed(header.seed);
+        context.replaying = true;
+
+        context.replay_continue = header.settings.get("continue").unwrap_or(false);
+
+        own_config = Some(context.config.clone());
+        match Difficulty::read(&header.settings) {
+            Ok(difficulty) => difficulty.save(&mut context.config),
+            Err(error) => warn!("Replay has no difficulty, playing at the configured one: {}", error),
+        }
 
-    let graphic_renderer = GraphicRenderer::new();
+        // Endless runs are made from the gameplay stream unless one was set
+        match header.settings.get_str("endless_seed") {
+            Some(seed) => context.config.set("endless_seed", seed),
+            None => context.config.remove("endless_seed"),
+        }
+    }
 
-    let mut views: Vec<Box<View>> = vec![Box::new(MainMenuView::new(&mut context))];
+    let mut views = ViewStack::new(Box::new(MainMenuView::new(&mut context)));
+    crash::set_views(views.get_names());
+    let mut transition: Option<ViewTransition> = None;
     let mut input = Vec::new();
 
-    // Seed the timers
+    // Seed the timers.  Logic always advances by exactly one logic interval.
     let mut input_timer = Timer::new(1.0 / 30.0);
     let mut logic_timer = Timer::new(1.0 / 60.0);
     let mut render_timer = Timer::new(1.0 / 60.0);
     let mut fps_timer = Timer::new(1.0);
 
+    let exit_after_replay = context.config.get_or("replay_exit", false);
+
+    let record_path = context.config.get("record").map(PathBuf::from);
+    let recorder = record_path.as_ref().map(|path| {
+        let mut settings = SaveData::new();
+        Difficulty::load(&context.config).write(&mut settings);
+        // As the main menu decides whether to show it
+        settings.set("continue", save::latest_slot().is_some());
+        if let Some(seed) = context.config.get("endless_seed") {
+            settings.set("endless_seed", seed);
+        }
+
+        let recorder = Rc::new(RefCell::new(Recorder::new(context.random.get_seed(), logic_timer.interval_ns(), settings)));
+        crash::set_replay(recorder.clone(), path.clone());
+        recorder
+    });
+
     let mut frames_rendered = 0;
     fps_timer.reset();
 
@@ -70,58 +174,172 @@ fn main() {
 
         // Gather input
         if input_timer.sprung() {
+            let mut resized = None;
+
             for event in context.sdl_event_pump.poll_iter() {
-                match input_translator.translate(&event) {
-                    Some(Input::Exit) => { break 'main; },
-                    Some(i) => { input.push(i); },
-                    None => {},
+                if let Event::Window { win_event_id: WindowEventId::Resized, data1, data2, .. } = event {
+                    resized = Some((data1 as u32, data2 as u32));
+                }
+
+                for i in context.input_translator.translate(&event) {
+                    match i {
+                        Input::Exit => {
+                            info!("Exit requested");
+                            break 'main;
+                        },
+                        // While replaying, only the debug controls get through
+                        Input::DebugTogglePause |
+                        Input::DebugStep |
+                        Input::DebugSlower |
+                        Input::DebugFaster => { input.push(i); },
+                        i => {
+                            if player.is_none() {
+                                input.push(i);
+                            }
+                        },
+                    }
+                }
+            }
+
+            if let Some((width, height)) = resized {
+                context.resize(width, height);
+
+                views.resize(&mut context);
+
+                if let Some(ref mut transition) = transition {
+                    transition.resize(&mut context);
                 }
             }
 
             input_timer.reset_with_overflow();
         }
 
-        // Update views
-        if logic_timer.sprung() {
-            views = {
-                let mut new_views = Vec::new();
-
-                'view: for mut view in views {
-                    let (result, pass_input) = view.update(&mut context, input, logic_timer.elapsed_ns());
-                    input = pass_input;
-
-                    match result {
-                        Some(action) => {
-                            match action {
-                                ViewAction::SetView(set_view) => {
-                                    new_views.clear();
-                                    new_views.push(set_view);
-                                    break 'view;
-                                },
-                                ViewAction::AddView(add_view) => {
-                                    new_views.insert(0, add_view);
-                                    new_views.push(view);
-                                },
-                                ViewAction::RemoveSelf => {},
-                                ViewAction::ExitGame => { break 'main; },
-                            }
-                        },
-                        None => {
-                            new_views.push(view);
+        // Update views in fixed steps, catching up on any that were missed
+        let mut steps = 0;
+        while logic_timer.sprung() {
+            if steps == MAX_CATCH_UP_STEPS {
+                debug!("Logic fell behind; dropping {} ns", -logic_timer.remainder_ns());
+                logic_timer.reset();
+                break;
+            }
+
+            // The debug clock controls never reach the views
+            input.retain(|i| {
+                match *i {
+                    Input::DebugTogglePause => {
+                        if debug_keys {
+                            if context.clock.is_paused() { context.clock.resume(); } else { context.clock.pause(); }
+                            debug!("Game clock {}", if context.clock.is_paused() { "paused" } else { "resumed" });
+                        }
+                    },
+                    Input::DebugStep => {
+                        if debug_keys { context.clock.step(); }
+                    },
+                    Input::DebugSlower | Input::DebugFaster => {
+                        if debug_keys {
+                            let scale = context.clock.get_scale();
+                            context.clock.set_scale(if *i == Input::DebugSlower { scale / 2.0 } else { scale * 2.0 });
+                            debug!("Time scale {}", context.clock.get_scale());
+                        }
+                    },
+                    _ => return true,
+                }
+                false
+            });
+
+            let mut elapsed_ns = context.clock.tick(logic_timer.interval_ns());
+
+            // The replay stands still while the clock is paused, so it can be stepped through
+            let replay_finished = if !context.clock.is_paused() || elapsed_ns > 0 {
+                match player {
+                    Some(ref mut player) => match player.next_tick() {
+                        Some((replayed_input, replayed_elapsed_ns)) => {
+                            input = replayed_input;
+                            elapsed_ns = replayed_elapsed_ns;
+                            false
                         },
-                    }
+                        None => true,
+                    },
+                    None => false,
+                }
+            } else {
+                false
+            };
+
+            if replay_finished {
+                info!("Replay finished");
+                player = None;
+                context.replaying = false;
+
+                if let Some(config) = own_config.take() {
+                    context.config = config;
                 }
 
-                new_views
+                if exit_after_replay {
+                    break 'main;
+                }
+            }
+
+            // Input is blocked while views change over
+            if transition.is_some() {
+                input.clear();
+            }
+
+            if let Some(ref recorder) = recorder {
+                recorder.borrow_mut().record_tick(&input, elapsed_ns);
+            }
+
+            input_state.update(&input, logic_timer.interval_ns());
+
+            // Keys let go while the window is in the background are never heard about
+            if input.contains(&Input::FocusLost) {
+                input_state.clear();
+            }
+
+            // Transitions run on the logic interval rather than the game
+            // clock, so they take the same time when it's paused or replayed
+            let transitioning = match transition {
+                Some(ref mut transition) => !transition.advance(logic_timer.interval_ns()),
+                None => false,
             };
 
-            input.clear();
+            if transitioning {
+                input.clear();
+                logic_timer.reset_with_overflow();
+                steps += 1;
+                continue;
+            }
+            transition = None;
+
+            let top_view = views.get_names().first().cloned();
+
+            match views.update(&mut context, input, &input_state, elapsed_ns) {
+                Some(StackEvent::Transition(kind, outgoing)) => {
+                    transition = Some(ViewTransition::new(kind, outgoing));
+                },
+                Some(StackEvent::Exit) => {
+                    info!("Exiting game");
+                    break 'main;
+                },
+                None => {},
+            }
+            crash::set_views(views.get_names());
+
+            // A view that just came up, like the pause menu, shouldn't see
+            // directions still held for the one before it repeat
+            if views.get_names().first().cloned() != top_view {
+                input_state.clear();
+            }
+
+            input = Vec::new();
+
             logic_timer.reset_with_overflow();
+            steps += 1;
         }
 
         // Render graphics
         if render_timer.sprung() {
-            graphic_renderer.render(&mut context, &views);
+            graphic_renderer.render(&mut context, &views, transition.as_mut(), logic_timer.progress());
             frames_rendered += 1;
 
             render_timer.reset_with_overflow();
@@ -129,7 +347,7 @@ fn main() {
 
         // Calculate FPS
         if fps_timer.sprung() {
-            debug!("FPS: {:.1}", frames_rendered as f32 / (fps_timer.elapsed_ns() as f32 / 1_000_000_000.0));
+            trace!("FPS: {:.1}", frames_rendered as f32 / (fps_timer.elapsed_ns() as f32 / 1_000_000_000.0));
             context.fps = frames_rendered;
             frames_rendered = 0;
             fps_timer.reset_with_overflow();
@@ -149,4 +367,15 @@ fn main() {
         };
         std::thread::sleep(std::time::Duration::new(0, sleep));
     }
+
+    // Playing back a replay shouldn't overwrite the player's saves
+    if !context.replaying {
+        views.exit(&mut context);
+    }
+
+    if let (Some(recorder), Some(path)) = (recorder, record_path) {
+        if let Err(error) = recorder.borrow().save(&path) {
+            error!("Could not save replay {}: {}", path.display(), error);
+        }
+    }
 }
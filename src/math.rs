@@ -55,6 +55,11 @@ impl Vector2 {
             self.y.abs(),
         )
     }
+
+    /// Returns the point t of the way from self to other
+    pub fn lerp(&self, other: Vector2, t: f32) -> Vector2 {
+        *self + (other - *self) * t
+    }
 }
 
 impl Add for Vector2 {
@@ -146,6 +151,15 @@ impl Transform {
             position: Vector2::zero(),
         }
     }
+
+    /// Returns the transform t of the way from self to other
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        Transform {
+            scale: self.scale.lerp(other.scale, t),
+            rotation: self.rotation + (other.rotation - self.rotation) * t,
+            position: self.position.lerp(other.position, t),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -171,6 +185,10 @@ impl Rectangle {
         self.size
     }
 
+    pub fn get_center(&self) -> Vector2 {
+        self.position + self.size / 2.0
+    }
+
     pub fn set_size(&mut self, size: Vector2) {
         self.size = Vector2::new(
             if size.x < 0.0 { 0.0 } else { size.x },
@@ -0,0 +1,157 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use time;
+
+/// For anything that affects the outcome of a game
+pub const GAMEPLAY: &'static str = "gameplay";
+
+/// For effects that don't, like particles, so they can't disturb the gameplay sequence
+pub const COSMETIC: &'static str = "cosmetic";
+
+/// A small xorshift64* generator.  It only uses integer arithmetic, so a
+/// seed produces the same sequence on every platform.
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng {
+            // A zero state would only ever produce zeros
+            state: match split_mix(seed) {
+                0 => 0x9e3779b97f4a7c15,
+                state => state,
+            },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+
+    /// Returns a value from 0 up to, but not including, 1
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Returns a value from min up to, but not including, max
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        if max <= min {
+            return min;
+        }
+
+        let span = (max as i64 - min as i64) as u64;
+        (min as i64 + (self.next_u64() % span) as i64) as i32
+    }
+
+    /// Returns a value from min up to, but not including, max
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// Returns true with the given probability, from 0 to 1
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+
+    /// The internal state, for saving a game mid-sequence
+    pub fn get_state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn set_state(&mut self, state: u64) {
+        if state != 0 {
+            self.state = state;
+        }
+    }
+}
+
+/// Independent named RNG streams, all derived from one seed.  Each stream's
+/// sequence depends only on the seed and its name, not on how much the other
+/// streams have been used.
+pub struct Random {
+    seed: u64,
+    streams: Vec<(&'static str, Rng)>,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random {
+            seed: seed,
+            streams: Vec::new(),
+        }
+    }
+
+    /// Picks a seed from the clock, for when the player hasn't given one
+    pub fn random_seed() -> u64 {
+        let now = time::get_time();
+        split_mix(now.sec as u64 ^ (now.nsec as u64) << 32 ^ time::precise_time_ns())
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Starts every stream over from a new seed
+    pub fn reseed(&mut self, seed: u64) {
+        debug!("Reseeding with {}", seed);
+        self.seed = seed;
+        self.streams.clear();
+    }
+
+    pub fn stream(&mut self, name: &'static str) -> &mut Rng {
+        let index = match self.streams.iter().position(|&(n, _)| n == name) {
+            Some(index) => index,
+            None => {
+                self.streams.push((name, Rng::new(self.seed ^ hash_name(name))));
+                self.streams.len() - 1
+            },
+        };
+
+        &mut self.streams[index].1
+    }
+
+    /// Returns every stream's state that has been used, for saving
+    pub fn get_states(&self) -> Vec<(&'static str, u64)> {
+        self.streams.iter().map(|&(name, ref rng)| (name, rng.get_state())).collect()
+    }
+
+    pub fn set_state(&mut self, name: &'static str, state: u64) {
+        self.stream(name).set_state(state);
+    }
+}
+
+fn split_mix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// FNV-1a, so stream seeds don't depend on the standard library's hasher
+fn hash_name(name: &str) -> u64 {
+    name.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,546 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+//! Replays are the input handed to the views on every logic tick, so that a
+//! session can be played back exactly.
+//!
+//! The file is a header (magic, format version, build version, RNG seed,
+//! logic interval and the settings the game depends on, like the difficulty)
+//! followed by tick records.  Each record starts with the
+//! number of plain ticks before it, those with no input and a full interval of
+//! game time, then a flags byte saying whether it has input, a non-standard
+//! elapsed time, or marks the end of the replay.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use sdl2::controller::Button as ControllerButton;
+use sdl2::keyboard::Keycode;
+
+use input::{Action, Binding, Input};
+use math::Vector2;
+use save::SaveData;
+
+const MAGIC: &'static [u8] = b"RPLY";
+const FORMAT_VERSION: u16 = 2;
+
+const FLAG_INPUT: u8 = 0x01;
+const FLAG_ELAPSED: u8 = 0x02;
+const FLAG_END: u8 = 0x80;
+
+pub struct ReplayHeader {
+    pub build_version: String,
+    pub seed: u64,
+    pub interval_ns: i64,
+    /// Config the views read when they start, in the keys a save uses
+    pub settings: SaveData,
+}
+
+/// Collects ticks in memory and writes them out when finished
+pub struct Recorder {
+    header: ReplayHeader,
+    body: Vec<u8>,
+
+    plain_ticks: u64,
+    ticks: u64,
+}
+
+impl Recorder {
+    pub fn new(seed: u64, interval_ns: i64, settings: SaveData) -> Recorder {
+        Recorder {
+            header: ReplayHeader {
+                build_version: env!("CARGO_PKG_VERSION").to_string(),
+                seed: seed,
+                interval_ns: interval_ns,
+                settings: settings,
+            },
+            body: Vec::new(),
+
+            plain_ticks: 0,
+            ticks: 0,
+        }
+    }
+
+    pub fn record_tick(&mut self, input: &Vec<Input>, elapsed_ns: i64) {
+        self.ticks += 1;
+
+        // Filtered before counting, so the count matches what's written
+        let input = input.iter().filter(|i| is_recorded(i)).collect::<Vec<&Input>>();
+
+        let mut flags = 0;
+        if !input.is_empty() {
+            flags |= FLAG_INPUT;
+        }
+        if elapsed_ns != self.header.interval_ns {
+            flags |= FLAG_ELAPSED;
+        }
+
+        if flags == 0 {
+            self.plain_ticks += 1;
+            return;
+        }
+
+        write_varint(&mut self.body, self.plain_ticks);
+        self.plain_ticks = 0;
+        self.body.push(flags);
+
+        if flags & FLAG_ELAPSED != 0 {
+            write_varint(&mut self.body, elapsed_ns as u64);
+        }
+
+        if flags & FLAG_INPUT != 0 {
+            write_varint(&mut self.body, input.len() as u64);
+            for &i in input.iter() {
+                write_input(&mut self.body, i);
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut buffer = Vec::new();
+
+        buffer.extend_from_slice(MAGIC);
+        buffer.push(FORMAT_VERSION as u8);
+        buffer.push((FORMAT_VERSION >> 8) as u8);
+        write_string(&mut buffer, &self.header.build_version);
+        write_u64(&mut buffer, self.header.seed);
+        write_varint(&mut buffer, self.header.interval_ns as u64);
+
+        let settings = self.header.settings.get_entries();
+        write_varint(&mut buffer, settings.len() as u64);
+        for &(ref key, ref value) in settings.iter() {
+            write_string(&mut buffer, key);
+            write_string(&mut buffer, value);
+        }
+
+        buffer.extend_from_slice(&self.body);
+        write_varint(&mut buffer, self.plain_ticks);
+        buffer.push(FLAG_END);
+
+        let mut file = try!(File::create(path));
+        try!(file.write_all(&buffer));
+
+        info!("Saved {} ticks of replay to {}", self.ticks, path.display());
+        Ok(())
+    }
+}
+
+/// Hands back recorded ticks in order
+pub struct Player {
+    header: ReplayHeader,
+    data: Vec<u8>,
+    position: usize,
+
+    plain_ticks: u64,
+    finished: bool,
+}
+
+impl Player {
+    pub fn load(path: &Path) -> io::Result<Player> {
+        let mut data = Vec::new();
+        try!(File::open(path).and_then(|mut file| file.read_to_end(&mut data)));
+
+        let mut player = Player {
+            header: ReplayHeader {
+                build_version: String::new(),
+                seed: 0,
+                interval_ns: 0,
+                settings: SaveData::new(),
+            },
+            data: data,
+            position: 0,
+
+            plain_ticks: 0,
+            finished: false,
+        };
+
+        if try!(player.read_bytes(MAGIC.len())) != MAGIC {
+            return Err(invalid("not a replay file"));
+        }
+
+        let version = try!(player.read_u8()) as u16 | (try!(player.read_u8()) as u16) << 8;
+        if version != FORMAT_VERSION {
+            return Err(invalid("unsupported replay format version"));
+        }
+
+        player.header.build_version = try!(player.read_string());
+        player.header.seed = try!(player.read_u64());
+        player.header.interval_ns = try!(player.read_varint()) as i64;
+
+        let settings = try!(player.read_varint());
+        for _ in 0..settings {
+            let key = try!(player.read_string());
+            let value = try!(player.read_string());
+            player.header.settings.set(&key, value);
+        }
+
+        if player.header.build_version != env!("CARGO_PKG_VERSION") {
+            warn!("Replay was recorded with v{}; it may not play back faithfully", player.header.build_version);
+        }
+
+        player.plain_ticks = try!(player.read_varint());
+
+        Ok(player)
+    }
+
+    pub fn get_header(&self) -> &ReplayHeader {
+        &self.header
+    }
+
+    /// Returns the next tick's input and elapsed game time, or None at the end
+    pub fn next_tick(&mut self) -> Option<(Vec<Input>, i64)> {
+        if self.finished {
+            return None;
+        }
+
+        if self.plain_ticks > 0 {
+            self.plain_ticks -= 1;
+            return Some((Vec::new(), self.header.interval_ns));
+        }
+
+        match self.read_record() {
+            Ok(Some(tick)) => Some(tick),
+            Ok(None) => {
+                self.finished = true;
+                None
+            },
+            Err(error) => {
+                error!("Replay is corrupt: {}", error);
+                self.finished = true;
+                None
+            },
+        }
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<(Vec<Input>, i64)>> {
+        let flags = try!(self.read_u8());
+        if flags & FLAG_END != 0 {
+            return Ok(None);
+        }
+
+        let elapsed_ns = if flags & FLAG_ELAPSED != 0 {
+            try!(self.read_varint()) as i64
+        } else {
+            self.header.interval_ns
+        };
+
+        let mut input = Vec::new();
+        if flags & FLAG_INPUT != 0 {
+            let count = try!(self.read_varint());
+            for _ in 0..count {
+                input.push(try!(self.read_input()));
+            }
+        }
+
+        self.plain_ticks = try!(self.read_varint());
+
+        Ok(Some((input, elapsed_ns)))
+    }
+
+    fn read_input(&mut self) -> io::Result<Input> {
+        Ok(match try!(self.read_u8()) {
+            0 => Input::Up,
+            1 => Input::Down,
+            2 => Input::Left,
+            3 => Input::Right,
+            4 => Input::Enter,
+            5 => Input::Pause,
+            6 => Input::MouseMotion(try!(self.read_vector())),
+            7 => Input::JoystickMotion(try!(self.read_vector())),
+            8 => Input::Release(try!(self.read_action())),
+            9 => Input::Bind(match try!(self.read_u8()) {
+                0 => match Keycode::from_i32(try!(self.read_varint()) as u32 as i32) {
+                    Some(keycode) => Binding::Key(keycode),
+                    None => return Err(invalid("unknown keycode")),
+                },
+                1 => Binding::Mouse(try!(self.read_u8())),
+                2 => match ControllerButton::from_string(&try!(self.read_string())) {
+                    Some(button) => Binding::Controller(button),
+                    None => return Err(invalid("unknown controller button")),
+                },
+                _ => return Err(invalid("unknown binding")),
+            }),
+            10 => Input::PointerDown(try!(self.read_vector())),
+            11 => Input::PointerUp(try!(self.read_vector())),
+            12 => Input::Text(try!(self.read_string())),
+            13 => Input::Backspace,
+            14 => Input::Commit,
+            15 => Input::FocusLost,
+            16 => Input::Delete,
+            17 => Input::Home,
+            18 => Input::End,
+            _ => return Err(invalid("unknown input")),
+        })
+    }
+
+    fn read_action(&mut self) -> io::Result<Action> {
+        Ok(match try!(self.read_u8()) {
+            0 => Action::Up,
+            1 => Action::Down,
+            2 => Action::Left,
+            3 => Action::Right,
+            4 => Action::Enter,
+            5 => Action::Pause,
+            _ => return Err(invalid("unknown action")),
+        })
+    }
+
+    fn read_vector(&mut self) -> io::Result<Vector2> {
+        let x = try!(self.read_u32());
+        let y = try!(self.read_u32());
+        Ok(Vector2::new(f32_from_bits(x), f32_from_bits(y)))
+    }
+
+    fn read_bytes(&mut self, count: usize) -> io::Result<&[u8]> {
+        if self.position + count > self.data.len() {
+            return Err(invalid("unexpected end of replay"));
+        }
+
+        self.position += count;
+        Ok(&self.data[self.position - count..self.position])
+    }
+
+    fn read_u8(&mut self) -> io::Result<u8> {
+        Ok(try!(self.read_bytes(1))[0])
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32> {
+        let bytes = try!(self.read_bytes(4));
+        Ok(bytes.iter().rev().fold(0, |value, byte| value << 8 | *byte as u32))
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        let bytes = try!(self.read_bytes(8));
+        Ok(bytes.iter().rev().fold(0, |value, byte| value << 8 | *byte as u64))
+    }
+
+    fn read_varint(&mut self) -> io::Result<u64> {
+        let mut value = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = try!(self.read_u8());
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+
+            shift += 7;
+            if shift > 63 {
+                return Err(invalid("malformed number"));
+            }
+        }
+    }
+
+    fn read_string(&mut self) -> io::Result<String> {
+        let length = try!(self.read_varint()) as usize;
+        let bytes = try!(self.read_bytes(length)).to_vec();
+        String::from_utf8(bytes).map_err(|_| invalid("malformed string"))
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_input(buffer: &mut Vec<u8>, input: &Input) {
+    match *input {
+        Input::Up => buffer.push(0),
+        Input::Down => buffer.push(1),
+        Input::Left => buffer.push(2),
+        Input::Right => buffer.push(3),
+        Input::Enter => buffer.push(4),
+        Input::Pause => buffer.push(5),
+        Input::MouseMotion(position) => {
+            buffer.push(6);
+            write_vector(buffer, position);
+        },
+        Input::JoystickMotion(position) => {
+            buffer.push(7);
+            write_vector(buffer, position);
+        },
+        Input::Release(action) => {
+            buffer.push(8);
+            buffer.push(action as u8);
+        },
+        Input::Bind(binding) => {
+            buffer.push(9);
+            match binding {
+                Binding::Key(keycode) => {
+                    buffer.push(0);
+                    write_varint(buffer, keycode as i32 as u32 as u64);
+                },
+                Binding::Mouse(button) => {
+                    buffer.push(1);
+                    buffer.push(button);
+                },
+                Binding::Controller(button) => {
+                    buffer.push(2);
+                    write_string(buffer, &button.string());
+                },
+            }
+        },
+        Input::PointerDown(position) => {
+            buffer.push(10);
+            write_vector(buffer, position);
+        },
+        Input::PointerUp(position) => {
+            buffer.push(11);
+            write_vector(buffer, position);
+        },
+        Input::Text(ref text) => {
+            buffer.push(12);
+            write_string(buffer, text);
+        },
+        Input::Backspace => buffer.push(13),
+        Input::Commit => buffer.push(14),
+        Input::FocusLost => buffer.push(15),
+        Input::Delete => buffer.push(16),
+        Input::Home => buffer.push(17),
+        Input::End => buffer.push(18),
+        // Left out by record_tick
+        Input::Exit |
+        Input::DebugTogglePause |
+        Input::DebugStep |
+        Input::DebugSlower |
+        Input::DebugFaster => {},
+    }
+}
+
+/// Whether an input is one the views see, and so goes in replays
+fn is_recorded(input: &Input) -> bool {
+    match *input {
+        Input::Exit |
+        Input::DebugTogglePause |
+        Input::DebugStep |
+        Input::DebugSlower |
+        Input::DebugFaster => false,
+        _ => true,
+    }
+}
+
+fn write_vector(buffer: &mut Vec<u8>, vector: Vector2) {
+    write_u32(buffer, f32_to_bits(vector.x));
+    write_u32(buffer, f32_to_bits(vector.y));
+}
+
+fn write_u32(buffer: &mut Vec<u8>, value: u32) {
+    for shift in 0..4 {
+        buffer.push((value >> (shift * 8)) as u8);
+    }
+}
+
+fn write_u64(buffer: &mut Vec<u8>, value: u64) {
+    for shift in 0..8 {
+        buffer.push((value >> (shift * 8)) as u8);
+    }
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            buffer.push(byte);
+            return;
+        }
+        buffer.push(byte | 0x80);
+    }
+}
+
+fn write_string(buffer: &mut Vec<u8>, s: &str) {
+    write_varint(buffer, s.len() as u64);
+    buffer.extend_from_slice(s.as_bytes());
+}
+
+fn f32_to_bits(value: f32) -> u32 {
+    unsafe { ::std::mem::transmute(value) }
+}
+
+fn f32_from_bits(bits: u32) -> f32 {
+    unsafe { ::std::mem::transmute(bits) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use input::{Action, Binding, Input};
+    use math::Vector2;
+    use save::SaveData;
+    use super::{Player, Recorder};
+
+    const INTERVAL_NS: i64 = 16_666_666;
+
+    #[test]
+    fn ticks_play_back_as_recorded() {
+        let mut ticks = Vec::new();
+
+        // Long enough runs of plain ticks to need more than one varint byte
+        ticks.push((vec![Input::Up, Input::Release(Action::Up)], INTERVAL_NS));
+        for _ in 0..300 {
+            ticks.push((Vec::new(), INTERVAL_NS));
+        }
+        ticks.push((Vec::new(), INTERVAL_NS / 2));
+        ticks.push((vec![Input::PointerDown(Vector2::new(0.25, 0.75)), Input::Text("né".to_string())], 0));
+        ticks.push((vec![Input::Bind(Binding::Mouse(3)), Input::Delete, Input::FocusLost], INTERVAL_NS));
+        for _ in 0..20_000 {
+            ticks.push((Vec::new(), INTERVAL_NS));
+        }
+        ticks.push((vec![Input::Commit], 1_000_000_000_000));
+
+        let mut settings = SaveData::new();
+        settings.set("difficulty", "custom");
+        settings.set("difficulty.speed", 1.25);
+
+        let mut recorder = Recorder::new(42, INTERVAL_NS, settings);
+        for &(ref input, elapsed_ns) in ticks.iter() {
+            recorder.record_tick(input, elapsed_ns);
+        }
+        // Never recorded, so the tick plays back empty
+        recorder.record_tick(&vec![Input::DebugStep], INTERVAL_NS);
+        ticks.push((Vec::new(), INTERVAL_NS));
+
+        let path = env::temp_dir().join("reaping-replay-test.rply");
+        recorder.save(&path).unwrap();
+        let player = Player::load(&path);
+        fs::remove_file(&path).ok();
+
+        let mut player = player.unwrap();
+        assert_eq!(player.get_header().seed, 42);
+        assert_eq!(player.get_header().interval_ns, INTERVAL_NS);
+        assert_eq!(player.get_header().settings.get_str("difficulty"), Some("custom"));
+        assert_eq!(player.get_header().settings.get::<f32>("difficulty.speed"), Ok(1.25));
+
+        for (index, &(ref input, elapsed_ns)) in ticks.iter().enumerate() {
+            match player.next_tick() {
+                Some((played_input, played_elapsed_ns)) => {
+                    assert!(played_input == *input, "tick {} has different input", index);
+                    assert_eq!(played_elapsed_ns, elapsed_ns, "tick {} has a different elapsed time", index);
+                },
+                None => panic!("replay ended at tick {} of {}", index, ticks.len()),
+            }
+        }
+        assert!(player.next_tick().is_none());
+    }
+}
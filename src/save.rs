@@ -0,0 +1,287 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+//! Runs in progress are saved to a handful of slots in the user data
+//! directory, as `key = value` lines like the config file.  Each save starts
+//! with its format version; older saves are brought up to date by running
+//! the migrations after their version in order, so a save survives updates
+//! as long as every format change adds a migration here.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use config::{self, user_data_dir};
+
+pub const SLOTS: usize = 3;
+
+const FORMAT_VERSION: u32 = 2;
+
+/// MIGRATIONS[n] turns a version n + 1 save into a version n + 2 one
+const MIGRATIONS: &'static [fn(&mut SaveData)] = &[
+    add_difficulty,
+];
+
+const SAVE_DIR: &'static str = "saves";
+
+/// The keys and values of one save
+pub struct SaveData {
+    entries: Vec<(String, String)>,
+}
+
+impl SaveData {
+    pub fn new() -> SaveData {
+        SaveData {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn set<T>(&mut self, key: &str, value: T) where T: ToString {
+        let value = value.to_string();
+
+        match self.entries.iter().position(|&(ref k, _)| k == key) {
+            Some(index) => self.entries[index].1 = value,
+            None => self.entries.push((key.to_string(), value)),
+        }
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.entries.iter().find(|&&(ref k, _)| k == key).map(|&(_, ref value)| value.as_str())
+    }
+
+    /// Returns the parsed value, or an error naming the key
+    pub fn get<T>(&self, key: &str) -> Result<T, String> where T: FromStr {
+        match self.get_str(key) {
+            Some(value) => value.parse().map_err(|_| format!("bad value for {}: {}", key, value)),
+            None => Err(format!("missing {}", key)),
+        }
+    }
+
+    /// Every key and value, in the order they were first set
+    pub fn get_entries(&self) -> &[(String, String)] {
+        &self.entries
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.entries.retain(|&(ref k, _)| k != key);
+    }
+
+}
+
+/// Version 2 added difficulty settings.  Every earlier run was played at
+/// what's now Normal.
+fn add_difficulty(data: &mut SaveData) {
+    data.set("difficulty", "normal");
+    data.set("difficulty.speed", 1.0);
+    data.set("difficulty.gaps", 1.0);
+    data.set("difficulty.time_limit", 30);
+    data.set("difficulty.lives", 3);
+    data.set("difficulty.hazards", 1.0);
+    data.set("difficulty.adaptive", false);
+    data.set("adaptive.deaths", "none");
+}
+
+fn slot_path(slot: usize) -> Option<PathBuf> {
+    user_data_dir().map(|dir| dir.join(SAVE_DIR).join(format!("slot{}.sav", slot + 1)))
+}
+
+pub fn exists(slot: usize) -> bool {
+    slot_path(slot).map(|path| path.is_file()).unwrap_or(false)
+}
+
+/// The slot saved to most recently, for the main menu's Continue
+pub fn latest_slot() -> Option<usize> {
+    (0..SLOTS)
+        .filter_map(|slot| modified(slot).map(|time| (slot, time)))
+        .max_by_key(|&(_, time)| time)
+        .map(|(slot, _)| slot)
+}
+
+/// An empty slot, or else the one saved to longest ago
+pub fn pick_slot() -> usize {
+    match (0..SLOTS).find(|&slot| !exists(slot)) {
+        Some(slot) => slot,
+        None => (0..SLOTS)
+            .filter_map(|slot| modified(slot).map(|time| (slot, time)))
+            .min_by_key(|&(_, time)| time)
+            .map(|(slot, _)| slot)
+            .unwrap_or(0),
+    }
+}
+
+fn modified(slot: usize) -> Option<::std::time::SystemTime> {
+    slot_path(slot)
+        .and_then(|path| fs::metadata(path).ok())
+        .and_then(|metadata| metadata.modified().ok())
+}
+
+pub fn write(slot: usize, data: &SaveData) -> io::Result<()> {
+    let path = match slot_path(slot) {
+        Some(path) => path,
+        None => return Err(io::Error::new(io::ErrorKind::NotFound, "no user data directory")),
+    };
+
+    if let Some(dir) = path.parent() {
+        try!(fs::create_dir_all(dir));
+    }
+
+    let mut file = try!(File::create(&path));
+    try!(writeln!(file, "version = {}", FORMAT_VERSION));
+    try!(writeln!(file, "build = {}", env!("CARGO_PKG_VERSION")));
+    for &(ref key, ref value) in data.entries.iter() {
+        try!(writeln!(file, "{} = {}", key, value));
+    }
+
+    info!("Saved the game to {}", path.display());
+    Ok(())
+}
+
+/// Reads a slot, migrating it to the current format
+pub fn read(slot: usize) -> io::Result<SaveData> {
+    match slot_path(slot) {
+        Some(path) => read_path(&path),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, "no user data directory")),
+    }
+}
+
+/// Reads a save from anywhere, e.g. a hand-made level to check
+pub fn read_path(path: &Path) -> io::Result<SaveData> {
+    let mut data = SaveData::new();
+    for line in BufReader::new(try!(File::open(path))).lines() {
+        if let Some((key, value)) = config::parse_line(&try!(line)) {
+            data.entries.push((key, value));
+        }
+    }
+
+    let version = match data.get::<u32>("version") {
+        Ok(version) if version >= 1 => version,
+        _ => return Err(invalid("save has no format version")),
+    };
+
+    if version > FORMAT_VERSION {
+        return Err(invalid("save is from a newer version of the game"));
+    }
+
+    for migration in MIGRATIONS[(version - 1) as usize..].iter() {
+        migration(&mut data);
+    }
+
+    if version < FORMAT_VERSION {
+        info!("Migrated {} from format version {} to {}", path.display(), version, FORMAT_VERSION);
+    }
+
+    data.remove("version");
+    data.remove("build");
+    Ok(data)
+}
+
+pub fn delete(slot: usize) {
+    if let Some(path) = slot_path(slot) {
+        if path.is_file() {
+            match fs::remove_file(&path) {
+                Ok(_) => debug!("Deleted {}", path.display()),
+                Err(error) => warn!("Could not delete {}: {}", path.display(), error),
+            }
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::{ErrorKind, Write};
+    use std::path::PathBuf;
+
+    use super::read_path;
+
+    fn write_save(name: &str, lines: &[&str]) -> PathBuf {
+        let path = env::temp_dir().join(name);
+        let mut file = File::create(&path).unwrap();
+        for line in lines.iter() {
+            writeln!(file, "{}", line).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn version_1_saves_are_migrated() {
+        let path = write_save("reaping-save-v1-test.sav", &[
+            "version = 1",
+            "build = 0.1.0",
+            "level = 4",
+            "frog.x = 6",
+        ]);
+        let data = read_path(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(data.get::<u32>("level"), Ok(4));
+        assert_eq!(data.get::<f32>("frog.x"), Ok(6.0));
+
+        // Everything before difficulty settings was played at Normal
+        assert_eq!(data.get_str("difficulty"), Some("normal"));
+        assert_eq!(data.get::<f32>("difficulty.speed"), Ok(1.0));
+        assert_eq!(data.get::<u32>("difficulty.lives"), Ok(3));
+        assert_eq!(data.get::<bool>("difficulty.adaptive"), Ok(false));
+        assert_eq!(data.get_str("adaptive.deaths"), Some("none"));
+
+        assert_eq!(data.get_str("version"), None);
+        assert_eq!(data.get_str("build"), None);
+    }
+
+    #[test]
+    fn current_saves_are_read_as_they_are() {
+        let path = write_save("reaping-save-v2-test.sav", &[
+            "version = 2",
+            "build = 0.1.0",
+            "difficulty = hard",
+            "difficulty.lives = 1",
+        ]);
+        let data = read_path(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(data.get_str("difficulty"), Some("hard"));
+        assert_eq!(data.get::<u32>("difficulty.lives"), Ok(1));
+        assert_eq!(data.get_str("difficulty.speed"), None);
+    }
+
+    #[test]
+    fn saves_need_a_known_version() {
+        for &(name, version) in [
+            ("reaping-save-newer-test.sav", "version = 3"),
+            ("reaping-save-zero-test.sav", "version = 0"),
+            ("reaping-save-bad-test.sav", "version = one"),
+            ("reaping-save-none-test.sav", "build = 0.1.0"),
+        ].iter() {
+            let path = write_save(name, &[version, "level = 1"]);
+            let result = read_path(&path);
+            fs::remove_file(&path).ok();
+
+            match result {
+                Err(error) => assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", version),
+                Ok(_) => panic!("{} was read", version),
+            }
+        }
+    }
+}
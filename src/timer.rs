@@ -53,4 +53,131 @@ impl Timer {
     pub fn elapsed_ns(&self) -> i64 {
         self.interval_ns as i64 - self.remainder_ns
     }
+
+    pub fn interval_ns(&self) -> i64 {
+        self.interval_ns as i64
+    }
+
+    /// Returns how far through the current interval the timer is, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        let progress = self.elapsed_ns() as f32 / self.interval_ns as f32;
+        if progress < 0.0 { 0.0 } else if progress > 1.0 { 1.0 } else { progress }
+    }
+}
+
+const MIN_TIME_SCALE: f64 = 0.1;
+const MAX_TIME_SCALE: f64 = 4.0;
+
+/// Turns real time into game time, which can be scaled, paused, or stepped
+/// one tick at a time.  Views are updated with game time; anything that
+/// should keep running while the game is paused uses real time instead.
+pub struct GameClock {
+    scale: f64,
+    paused: bool,
+    step_requested: bool,
+
+    /// Set by the game's own pause menu
+    frozen: bool,
+
+    /// Fractional nanoseconds left over from scaling, so slow motion doesn't drift
+    carry_ns: f64,
+
+    real_elapsed_ns: i64,
+    elapsed_ns: i64,
+}
+
+impl GameClock {
+    pub fn new() -> GameClock {
+        GameClock {
+            scale: 1.0,
+            paused: false,
+            step_requested: false,
+
+            frozen: false,
+
+            carry_ns: 0.0,
+
+            real_elapsed_ns: 0,
+            elapsed_ns: 0,
+        }
+    }
+
+    pub fn get_scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Sets the time scale, clamped to a sane range.  1.0 is normal speed.
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = if scale < MIN_TIME_SCALE {
+            MIN_TIME_SCALE
+        } else if scale > MAX_TIME_SCALE {
+            MAX_TIME_SCALE
+        } else {
+            scale
+        };
+        self.carry_ns = 0.0;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.step_requested = false;
+    }
+
+    /// While paused, lets exactly one tick of unscaled time through
+    pub fn step(&mut self) {
+        if self.paused {
+            self.step_requested = true;
+        }
+    }
+
+    /// Stops game time for an in-game pause.  Unlike pause, this doesn't hold
+    /// up replays, since the input that ends it is part of the replay.
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    pub fn thaw(&mut self) {
+        self.frozen = false;
+    }
+
+    /// Advances the clock by one tick of real time and returns the game time
+    /// that passed.
+    pub fn tick(&mut self, real_ns: i64) -> i64 {
+        self.real_elapsed_ns = real_ns;
+
+        self.elapsed_ns = if self.frozen {
+            0
+        } else if self.paused {
+            if self.step_requested {
+                self.step_requested = false;
+                real_ns
+            } else {
+                0
+            }
+        } else {
+            let scaled = real_ns as f64 * self.scale + self.carry_ns;
+            self.carry_ns = scaled.fract();
+            scaled.trunc() as i64
+        };
+
+        self.elapsed_ns
+    }
+
+    /// Game time that passed during the last tick
+    pub fn elapsed_ns(&self) -> i64 {
+        self.elapsed_ns
+    }
+
+    /// Real time that passed during the last tick, regardless of scale or pause
+    pub fn real_elapsed_ns(&self) -> i64 {
+        self.real_elapsed_ns
+    }
 }
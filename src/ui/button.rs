@@ -0,0 +1,109 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use context::Context;
+use graphics::{Align, Font, GraphicObject, Sprite};
+use input::Input;
+use math::Rectangle;
+use ui::{self, Response, Widget, WidgetEvent};
+
+/// A pressable button.  It can also show a value on its right, in which case
+/// its text moves to the left.
+pub struct Button {
+    text_sprite: Option<Sprite>,
+    value_sprite: Option<Sprite>,
+    region: Rectangle,
+
+    text: String,
+    value: Option<String>,
+    font: Font,
+
+    focused: bool,
+}
+
+impl Button {
+    pub fn new(context: &Context, font: &Font, text: &str, region: Rectangle) -> Button {
+        let mut button = Button {
+            text_sprite: None,
+            value_sprite: None,
+            region: region,
+
+            text: text.to_string(),
+            value: None,
+            font: font.clone(),
+
+            focused: false,
+        };
+
+        button.refresh(context);
+        button
+    }
+
+    /// Shows text on the right of the button, or removes it with None
+    pub fn set_value(&mut self, context: &Context, value: Option<&str>) {
+        self.value = value.map(|value| value.to_string());
+        self.refresh(context);
+    }
+
+    fn refresh(&mut self, context: &Context) {
+        let align = if self.value.is_some() { Align::Left } else { Align::Center };
+
+        self.text_sprite = ui::render_text(context, &self.font, &self.text, self.region, align);
+        self.value_sprite = match self.value {
+            Some(ref value) => ui::render_text(context, &self.font, value, self.region, Align::Right),
+            None => None,
+        };
+    }
+}
+
+impl Widget for Button {
+    fn get_region(&self) -> Rectangle {
+        self.region
+    }
+
+    fn set_region(&mut self, context: &Context, region: Rectangle) {
+        self.region = region;
+        self.refresh(context);
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn handle_input(&mut self, _: &mut Context, input: &Input) -> Response {
+        match *input {
+            Input::Enter => Response::Event(WidgetEvent::Pressed),
+            _ => Response::Ignored,
+        }
+    }
+}
+
+impl GraphicObject for Button {
+    fn draw(&self, context: &mut Context) {
+        ui::draw_background(context, self.region, self.focused);
+
+        if let Some(ref text_sprite) = self.text_sprite {
+            text_sprite.draw(context);
+        }
+
+        if let Some(ref value_sprite) = self.value_sprite {
+            value_sprite.draw(context);
+        }
+    }
+}
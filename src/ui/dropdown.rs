@@ -0,0 +1,251 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use context::Context;
+use graphics::{Align, Font, GraphicObject, Sprite};
+use input::Input;
+use math::{Rectangle, Vector2};
+use ui::{self, Response, Widget, WidgetEvent};
+
+struct DropdownOption {
+    text: String,
+
+    /// Shown on the closed dropdown
+    value_sprite: Option<Sprite>,
+    /// Shown in the open list
+    list_sprite: Option<Sprite>,
+    region: Rectangle,
+}
+
+/// Picks one of a list of options.  Enter opens the list below it, and left
+/// and right cycle through the options without opening it.
+pub struct Dropdown {
+    label_sprite: Option<Sprite>,
+    options: Vec<DropdownOption>,
+    region: Rectangle,
+
+    label: String,
+    font: Font,
+
+    selected: usize,
+    /// The option under the pointer or picked with up and down while open
+    hovered: Option<usize>,
+    open: bool,
+
+    focused: bool,
+}
+
+impl Dropdown {
+    pub fn new(context: &Context, font: &Font, label: &str, region: Rectangle, options: &[&str], selected: usize) -> Dropdown {
+        let options = options.iter().map(|option| {
+            DropdownOption {
+                text: option.to_string(),
+
+                value_sprite: None,
+                list_sprite: None,
+                region: region,
+            }
+        }).collect::<Vec<DropdownOption>>();
+
+        let selected = if selected < options.len() { selected } else { 0 };
+
+        let mut dropdown = Dropdown {
+            label_sprite: None,
+            options: options,
+            region: region,
+
+            label: label.to_string(),
+            font: font.clone(),
+
+            selected: selected,
+            hovered: None,
+            open: false,
+
+            focused: false,
+        };
+
+        dropdown.refresh(context);
+        dropdown
+    }
+
+    fn refresh(&mut self, context: &Context) {
+        let region = self.region;
+
+        let list_start = Vector2::new(
+            region.position.x + region.get_size().x / 2.0,
+            region.position.y + region.get_size().y,
+        );
+        let list_size = Vector2::new(
+            region.get_size().x / 2.0,
+            region.get_size().y,
+        );
+
+        self.label_sprite = ui::render_text(context, &self.font, &self.label, region, Align::Left);
+
+        for (index, option) in self.options.iter_mut().enumerate() {
+            option.region = Rectangle::new(
+                Vector2::new(
+                    list_start.x,
+                    index as f32 * list_size.y + list_start.y,
+                ),
+                list_size,
+            ).unwrap();
+
+            option.value_sprite = ui::render_text(context, &self.font, &option.text, region, Align::Right);
+            option.list_sprite = ui::render_text(context, &self.font, &option.text, option.region, Align::Left);
+        }
+    }
+
+    pub fn set_selected(&mut self, selected: usize) {
+        if selected < self.options.len() {
+            self.selected = selected;
+        }
+    }
+
+    fn select(&mut self, selected: usize) -> Response {
+        self.open = false;
+
+        if selected != self.selected {
+            self.selected = selected;
+            Response::Event(WidgetEvent::Selected(selected))
+        } else {
+            Response::Consumed
+        }
+    }
+
+    fn handle_open(&mut self, input: &Input) -> Response {
+        let count = self.options.len();
+
+        match *input {
+            Input::Up => {
+                self.hovered = Some(match self.hovered {
+                    Some(index) if index > 0 => index - 1,
+                    _ => count - 1,
+                });
+            },
+            Input::Down => {
+                self.hovered = Some(match self.hovered {
+                    Some(index) if index + 1 < count => index + 1,
+                    _ => 0,
+                });
+            },
+            Input::MouseMotion(position) => {
+                self.hovered = self.options.iter().position(|option| option.region.contains(position));
+            },
+            Input::Enter => {
+                return match self.hovered {
+                    Some(index) => self.select(index),
+                    None => {
+                        // Clicking away from the list closes it
+                        self.open = false;
+                        Response::Consumed
+                    },
+                };
+            },
+            Input::Pause => self.open = false,
+            _ => (),
+        }
+
+        Response::Consumed
+    }
+}
+
+impl Widget for Dropdown {
+    fn get_region(&self) -> Rectangle {
+        self.region
+    }
+
+    fn set_region(&mut self, context: &Context, region: Rectangle) {
+        self.region = region;
+        self.refresh(context);
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+
+        if !focused {
+            self.open = false;
+        }
+    }
+
+    fn is_capturing(&self) -> bool {
+        self.open
+    }
+
+    fn contains(&self, point: Vector2) -> bool {
+        self.region.contains(point) ||
+        (self.open && self.options.iter().any(|option| option.region.contains(point)))
+    }
+
+    fn handle_input(&mut self, _: &mut Context, input: &Input) -> Response {
+        if self.options.is_empty() {
+            return Response::Ignored;
+        }
+
+        if self.open {
+            return self.handle_open(input);
+        }
+
+        let count = self.options.len();
+
+        match *input {
+            Input::Enter => {
+                self.open = true;
+                self.hovered = Some(self.selected);
+                Response::Consumed
+            },
+            Input::Left => {
+                let selected = (self.selected + count - 1) % count;
+                self.select(selected)
+            },
+            Input::Right => {
+                let selected = (self.selected + 1) % count;
+                self.select(selected)
+            },
+            _ => Response::Ignored,
+        }
+    }
+}
+
+impl GraphicObject for Dropdown {
+    fn draw(&self, context: &mut Context) {
+        ui::draw_background(context, self.region, self.focused);
+
+        if let Some(ref label_sprite) = self.label_sprite {
+            label_sprite.draw(context);
+        }
+
+        if let Some(option) = self.options.get(self.selected) {
+            if let Some(ref value_sprite) = option.value_sprite {
+                value_sprite.draw(context);
+            }
+        }
+
+        if self.open {
+            for (index, option) in self.options.iter().enumerate() {
+                context.sdl_renderer.set_draw_color(if self.hovered == Some(index) { ui::WIDGET_HIGHLIGHT } else { ui::WIDGET_DARK });
+                context.sdl_renderer.fill_rect(option.region.to_sdl_rectangle());
+
+                if let Some(ref list_sprite) = option.list_sprite {
+                    list_sprite.draw(context);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use context::Context;
+use input::{Action, Input, InputState};
+use ui::{Response, Widget, WidgetEvent};
+
+/// Tracks which of a view's widgets has focus.  Up and down move between
+/// focusable widgets, the pointer focuses whatever it's over, and everything
+/// else goes to the focused widget first.
+pub struct Focus {
+    focused: Option<usize>,
+}
+
+impl Focus {
+    pub fn new() -> Focus {
+        Focus {
+            focused: None,
+        }
+    }
+
+    pub fn set_focused(&mut self, focused: Option<usize>) {
+        self.focused = focused;
+    }
+
+    /// Returns the first widget event, if any, and the inputs nothing used,
+    /// so the view can still see a Pause that no widget wanted.  Inputs after
+    /// the event aren't handled and count as unused.
+    pub fn update(&mut self, context: &mut Context, widgets: &mut Vec<&mut Widget>, input: &Vec<Input>, state: &InputState) -> (Option<(usize, WidgetEvent)>, Vec<Input>) {
+        if self.focused.map(|index| index >= widgets.len() || !widgets[index].is_focusable()).unwrap_or(false) {
+            self.focused = None;
+        }
+
+        // Held directions scroll through lists and step sliders
+        let mut repeats = Vec::new();
+        for action in [Action::Up, Action::Down, Action::Left, Action::Right].iter() {
+            if state.repeated(*action) {
+                repeats.push(action.to_input());
+            }
+        }
+
+        let mut event = None;
+        let mut unused = Vec::new();
+
+        for (index, i) in input.iter().chain(repeats.iter()).enumerate() {
+            let repeated = index >= input.len();
+
+            match self.handle(context, widgets, i) {
+                Some(Response::Event(widget_event)) => {
+                    event = self.focused.map(|focused| (focused, widget_event));
+
+                    // One event a tick; the rest is left for the view
+                    if !repeated {
+                        unused.extend(input[index + 1..].iter().cloned());
+                    }
+                    break;
+                },
+                Some(Response::Ignored) if !repeated => unused.push(i.clone()),
+                _ => (),
+            }
+        }
+
+        for (index, widget) in widgets.iter_mut().enumerate() {
+            widget.set_focused(self.focused == Some(index));
+        }
+
+        (event, unused)
+    }
+
+    /// Returns None when focus movement used the input
+    fn handle(&mut self, context: &mut Context, widgets: &mut Vec<&mut Widget>, input: &Input) -> Option<Response> {
+        if let Some(index) = self.focused {
+            if widgets[index].is_capturing() {
+                return Some(widgets[index].handle_input(context, input));
+            }
+        }
+
+        match *input {
+            Input::MouseMotion(position) | Input::PointerDown(position) => {
+                self.focused = widgets.iter().position(|widget| widget.is_focusable() && widget.contains(position));
+            },
+            _ => (),
+        }
+
+        if let Some(index) = self.focused {
+            match widgets[index].handle_input(context, input) {
+                Response::Ignored => (),
+                response => return Some(response),
+            }
+        }
+
+        match *input {
+            Input::Up => self.focused = self.next(widgets, false),
+            Input::Down => self.focused = self.next(widgets, true),
+            _ => return Some(Response::Ignored),
+        }
+
+        None
+    }
+
+    /// Finds the next focusable widget, wrapping around
+    fn next(&self, widgets: &Vec<&mut Widget>, forward: bool) -> Option<usize> {
+        let count = widgets.len();
+
+        let start = match self.focused {
+            Some(index) => index,
+            None => return widgets.iter().position(|widget| widget.is_focusable()),
+        };
+
+        for offset in 1..count + 1 {
+            let index = if forward { (start + offset) % count } else { (start + count - offset) % count };
+
+            if widgets[index].is_focusable() {
+                return Some(index);
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,79 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use context::Context;
+use graphics::{Align, Font, GraphicObject, Sprite};
+use input::Input;
+use math::Rectangle;
+use ui::{self, Response, Widget};
+
+/// Text that can't be focused
+pub struct Label {
+    text_sprite: Option<Sprite>,
+    region: Rectangle,
+
+    text: String,
+    font: Font,
+    align: Align,
+}
+
+impl Label {
+    pub fn new(context: &Context, font: &Font, text: &str, region: Rectangle, align: Align) -> Label {
+        Label {
+            text_sprite: ui::render_text(context, font, text, region, align),
+            region: region,
+
+            text: text.to_string(),
+            font: font.clone(),
+            align: align,
+        }
+    }
+
+    pub fn set_text(&mut self, context: &Context, text: &str) {
+        self.text = text.to_string();
+        self.text_sprite = ui::render_text(context, &self.font, text, self.region, self.align);
+    }
+}
+
+impl Widget for Label {
+    fn get_region(&self) -> Rectangle {
+        self.region
+    }
+
+    fn set_region(&mut self, context: &Context, region: Rectangle) {
+        self.region = region;
+        self.text_sprite = ui::render_text(context, &self.font, &self.text, region, self.align);
+    }
+
+    fn is_focusable(&self) -> bool {
+        false
+    }
+
+    fn handle_input(&mut self, _: &mut Context, _: &Input) -> Response {
+        Response::Ignored
+    }
+}
+
+impl GraphicObject for Label {
+    fn draw(&self, context: &mut Context) {
+        if let Some(ref text_sprite) = self.text_sprite {
+            text_sprite.draw(context);
+        }
+    }
+}
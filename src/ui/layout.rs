@@ -0,0 +1,291 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use math::{Rectangle, Vector2};
+
+/// Where a region sits inside its parent
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far along each axis the anchor is, from 0 at the start to 1 at the end
+    fn fractions(&self) -> Vector2 {
+        match *self {
+            Anchor::TopLeft => Vector2::new(0.0, 0.0),
+            Anchor::Top => Vector2::new(0.5, 0.0),
+            Anchor::TopRight => Vector2::new(1.0, 0.0),
+            Anchor::Left => Vector2::new(0.0, 0.5),
+            Anchor::Center => Vector2::new(0.5, 0.5),
+            Anchor::Right => Vector2::new(1.0, 0.5),
+            Anchor::BottomLeft => Vector2::new(0.0, 1.0),
+            Anchor::Bottom => Vector2::new(0.5, 1.0),
+            Anchor::BottomRight => Vector2::new(1.0, 1.0),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Length {
+    /// A percentage of the parent's size along the same axis
+    Percent(f32),
+    /// A percentage of the screen height, for things that should keep their
+    /// shape at any aspect ratio
+    ScreenHeight(f32),
+    Pixels(f32),
+}
+
+impl Length {
+    fn resolve(&self, parent: f32, screen_size: Vector2) -> f32 {
+        match *self {
+            Length::Percent(percent) => parent * percent / 100.0,
+            Length::ScreenHeight(percent) => screen_size.y * percent / 100.0,
+            Length::Pixels(pixels) => pixels,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Direction {
+    Vertical,
+    Horizontal,
+}
+
+/// A region in a Layout
+pub type Slot = usize;
+
+/// The whole screen, which every layout starts with
+pub const SCREEN: Slot = 0;
+
+struct Node {
+    parent: Slot,
+    anchor: Anchor,
+    width: Length,
+    height: Length,
+    margin: Length,
+
+    /// Set when the node's children are stacked instead of anchored
+    stack: Option<(Direction, Length)>,
+}
+
+/// A tree of regions described by anchors and relative sizes, so a view can
+/// work out where everything goes again whenever the screen size changes.
+///
+/// Children are anchored inside their parent, keeping margin away from the
+/// edges, unless the parent is a stack.  Then they're placed one after the
+/// other from its top or left, spacing apart, and their anchors only align
+/// them across the stack.
+pub struct Layout {
+    nodes: Vec<Node>,
+    regions: Vec<Rectangle>,
+}
+
+impl Layout {
+    pub fn new() -> Layout {
+        Layout {
+            nodes: vec![Node {
+                parent: SCREEN,
+                anchor: Anchor::TopLeft,
+                width: Length::Percent(100.0),
+                height: Length::Percent(100.0),
+                margin: Length::Pixels(0.0),
+
+                stack: None,
+            }],
+            regions: Vec::new(),
+        }
+    }
+
+    /// Parents have to be added before their children
+    pub fn add(&mut self, parent: Slot, anchor: Anchor, width: Length, height: Length) -> Slot {
+        assert!(parent < self.nodes.len(), "Layout parent {} doesn't exist", parent);
+
+        self.nodes.push(Node {
+            parent: parent,
+            anchor: anchor,
+            width: width,
+            height: height,
+            margin: Length::Pixels(0.0),
+
+            stack: None,
+        });
+
+        self.nodes.len() - 1
+    }
+
+    pub fn set_margin(&mut self, slot: Slot, margin: Length) {
+        self.nodes[slot].margin = margin;
+    }
+
+    /// Stacks the slot's children instead of anchoring them
+    pub fn set_stack(&mut self, slot: Slot, direction: Direction, spacing: Length) {
+        self.nodes[slot].stack = Some((direction, spacing));
+    }
+
+    /// Works out every region for a screen size
+    pub fn compute(&mut self, screen_size: Vector2) {
+        self.regions = Vec::with_capacity(self.nodes.len());
+        self.regions.push(Rectangle::new(Vector2::zero(), screen_size).unwrap());
+
+        // How far along each stack the next child goes
+        let mut cursors = vec![0.0; self.nodes.len()];
+
+        for node in self.nodes.iter().skip(1) {
+            let parent = self.regions[node.parent];
+            let parent_size = parent.get_size();
+
+            let size = Vector2::new(
+                node.width.resolve(parent_size.x, screen_size),
+                node.height.resolve(parent_size.y, screen_size),
+            );
+            let fractions = node.anchor.fractions();
+
+            let position = match self.nodes[node.parent].stack {
+                Some((direction, spacing)) => {
+                    let cursor = cursors[node.parent];
+
+                    match direction {
+                        Direction::Vertical => {
+                            cursors[node.parent] += size.y + spacing.resolve(parent_size.y, screen_size);
+
+                            Vector2::new(
+                                parent.position.x + (parent_size.x - size.x) * fractions.x,
+                                parent.position.y + cursor,
+                            )
+                        },
+                        Direction::Horizontal => {
+                            cursors[node.parent] += size.x + spacing.resolve(parent_size.x, screen_size);
+
+                            Vector2::new(
+                                parent.position.x + cursor,
+                                parent.position.y + (parent_size.y - size.y) * fractions.y,
+                            )
+                        },
+                    }
+                },
+                None => {
+                    let margin = Vector2::new(
+                        node.margin.resolve(parent_size.x, screen_size),
+                        node.margin.resolve(parent_size.y, screen_size),
+                    );
+
+                    parent.position + margin + (parent_size - margin * 2.0 - size) * fractions
+                },
+            };
+
+            self.regions.push(Rectangle::new(position, size).unwrap_or(Rectangle::new(position, Vector2::zero()).unwrap()));
+        }
+    }
+
+    /// The slot's region from the last compute
+    pub fn get(&self, slot: Slot) -> Rectangle {
+        self.regions[slot]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Vector2;
+    use super::{Anchor, Direction, Layout, Length, Slot, SCREEN};
+
+    fn screen() -> Vector2 {
+        Vector2::new(800.0, 600.0)
+    }
+
+    fn assert_region(layout: &Layout, slot: Slot, position: (f32, f32), size: (f32, f32)) {
+        let region = layout.get(slot);
+        assert_eq!(region.position, Vector2::new(position.0, position.1), "position of slot {}", slot);
+        assert_eq!(region.get_size(), Vector2::new(size.0, size.1), "size of slot {}", slot);
+    }
+
+    #[test]
+    fn anchors_keep_the_margin_from_the_parent_edges() {
+        let anchors = [
+            (Anchor::TopLeft, (10.0, 10.0)),
+            (Anchor::Top, (350.0, 10.0)),
+            (Anchor::TopRight, (690.0, 10.0)),
+            (Anchor::Left, (10.0, 275.0)),
+            (Anchor::Center, (350.0, 275.0)),
+            (Anchor::Right, (690.0, 275.0)),
+            (Anchor::BottomLeft, (10.0, 540.0)),
+            (Anchor::Bottom, (350.0, 540.0)),
+            (Anchor::BottomRight, (690.0, 540.0)),
+        ];
+
+        let mut layout = Layout::new();
+        let slots = anchors.iter().map(|&(anchor, _)| {
+            let slot = layout.add(SCREEN, anchor, Length::Pixels(100.0), Length::Pixels(50.0));
+            layout.set_margin(slot, Length::Pixels(10.0));
+            slot
+        }).collect::<Vec<Slot>>();
+
+        layout.compute(screen());
+
+        assert_region(&layout, SCREEN, (0.0, 0.0), (800.0, 600.0));
+        for (&slot, &(_, position)) in slots.iter().zip(anchors.iter()) {
+            assert_region(&layout, slot, position, (100.0, 50.0));
+        }
+    }
+
+    #[test]
+    fn lengths_resolve_against_parent_or_screen() {
+        let mut layout = Layout::new();
+        let half = layout.add(SCREEN, Anchor::BottomRight, Length::Percent(50.0), Length::Percent(50.0));
+        let child = layout.add(half, Anchor::TopLeft, Length::ScreenHeight(10.0), Length::Percent(50.0));
+        let fixed = layout.add(half, Anchor::TopLeft, Length::Pixels(25.0), Length::Pixels(30.0));
+
+        layout.compute(screen());
+        assert_region(&layout, half, (400.0, 300.0), (400.0, 300.0));
+        assert_region(&layout, child, (400.0, 300.0), (60.0, 150.0));
+        assert_region(&layout, fixed, (400.0, 300.0), (25.0, 30.0));
+
+        // Everything follows the screen size
+        layout.compute(Vector2::new(400.0, 300.0));
+        assert_region(&layout, half, (200.0, 150.0), (200.0, 150.0));
+        assert_region(&layout, child, (200.0, 150.0), (30.0, 75.0));
+        assert_region(&layout, fixed, (200.0, 150.0), (25.0, 30.0));
+    }
+
+    #[test]
+    fn vertical_stacks_go_down_with_spacing() {
+        let mut layout = Layout::new();
+        let menu = layout.add(SCREEN, Anchor::Top, Length::Pixels(200.0), Length::Pixels(500.0));
+        layout.set_stack(menu, Direction::Vertical, Length::Pixels(20.0));
+
+        // Anchors only line the children up across the stack
+        let first = layout.add(menu, Anchor::Center, Length::Percent(50.0), Length::Pixels(40.0));
+        let second = layout.add(menu, Anchor::BottomLeft, Length::Pixels(50.0), Length::Pixels(40.0));
+        let third = layout.add(menu, Anchor::Right, Length::Pixels(50.0), Length::Pixels(60.0));
+
+        layout.compute(screen());
+        assert_region(&layout, menu, (300.0, 0.0), (200.0, 500.0));
+        assert_region(&layout, first, (350.0, 0.0), (100.0, 40.0));
+        assert_region(&layout, second, (300.0, 60.0), (50.0, 40.0));
+        assert_region(&layout, third, (450.0, 120.0), (50.0, 60.0));
+    }
+}
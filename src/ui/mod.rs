@@ -0,0 +1,127 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+//! Widgets shared by the menu screens.  Views own their widgets and hand a
+//! list of them to a Focus each update, which moves focus between them and
+//! passes input to the focused one.
+
+use sdl2::pixels::Color;
+
+use context::Context;
+use graphics::{Align, Font, GraphicObject, Sprite};
+use input::Input;
+use math::{Rectangle, Vector2};
+
+pub const WIDGET_NORMAL: Color = Color::RGB(75, 185, 30);
+pub const WIDGET_HIGHLIGHT: Color = Color::RGB(100, 210, 50);
+pub const WIDGET_DARK: Color = Color::RGB(40, 110, 0);
+pub const WIDGET_TEXT: Color = Color::RGB(255, 255, 255);
+
+/// Something that happened to a widget, reported by Focus::update
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WidgetEvent {
+    Pressed,
+    Toggled(bool),
+    Changed(f32),
+    Selected(usize),
+}
+
+/// What a widget did with an input
+pub enum Response {
+    /// The input is free for focus movement or the view
+    Ignored,
+    Consumed,
+    Event(WidgetEvent),
+}
+
+pub trait Widget: GraphicObject {
+    fn get_region(&self) -> Rectangle;
+
+    /// Moves the widget, e.g. after its layout is recomputed
+    fn set_region(&mut self, context: &Context, region: Rectangle);
+
+    /// Labels and panels can't take focus
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focused(&mut self, _: bool) {}
+
+    /// Whether the widget wants every input, like an open dropdown does
+    fn is_capturing(&self) -> bool {
+        false
+    }
+
+    /// Whether the pointer is over the widget
+    fn contains(&self, point: Vector2) -> bool {
+        self.get_region().contains(point)
+    }
+
+    /// Handles one input while the widget is focused
+    fn handle_input(&mut self, context: &mut Context, input: &Input) -> Response;
+}
+
+/// Renders text and positions it vertically centered inside region
+pub fn render_text(context: &Context, font: &Font, text: &str, region: Rectangle, align: Align) -> Option<Sprite> {
+    if text.is_empty() {
+        return None;
+    }
+
+    let padding = context.rel.height(0.02);
+
+    context.font_renderer.render_sprite(context, font, text, WIDGET_TEXT).map(|mut sprite| {
+        let half_width = sprite.get_output_region().0.get_size().x / 2.0;
+
+        sprite.transform.position = Vector2::new(
+            match align {
+                Align::Left => region.position.x + padding + half_width,
+                Align::Center => region.position.x + region.get_size().x / 2.0,
+                Align::Right => region.position.x + region.get_size().x - padding - half_width,
+            },
+            region.position.y + region.get_size().y / 2.0,
+        );
+        sprite
+    })
+}
+
+/// Fills region with the normal or highlighted widget colour
+pub fn draw_background(context: &mut Context, region: Rectangle, focused: bool) {
+    context.sdl_renderer.set_draw_color(if focused { WIDGET_HIGHLIGHT } else { WIDGET_NORMAL });
+    context.sdl_renderer.fill_rect(region.to_sdl_rectangle());
+}
+
+pub use self::button::Button;
+pub use self::dropdown::Dropdown;
+pub use self::focus::Focus;
+pub use self::label::Label;
+pub use self::layout::{Anchor, Direction, Layout, Length, Slot, SCREEN};
+pub use self::panel::Panel;
+pub use self::slider::Slider;
+pub use self::text_field::{TextField, TextFieldEvent};
+pub use self::toggle::Toggle;
+
+mod button;
+mod dropdown;
+mod focus;
+mod label;
+mod layout;
+mod panel;
+mod slider;
+mod text_field;
+mod toggle;
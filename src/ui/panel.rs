@@ -0,0 +1,81 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use sdl2::pixels::Color;
+use sdl2::render::BlendMode;
+
+use context::Context;
+use graphics::GraphicObject;
+use input::Input;
+use math::Rectangle;
+use ui::{Response, Widget};
+
+/// A coloured backdrop that other widgets are drawn over.  Colours with alpha
+/// are blended over what's behind them.
+pub struct Panel {
+    region: Rectangle,
+    color: Color,
+}
+
+impl Panel {
+    pub fn new(region: Rectangle, color: Color) -> Panel {
+        Panel {
+            region: region,
+            color: color,
+        }
+    }
+}
+
+impl Widget for Panel {
+    fn get_region(&self) -> Rectangle {
+        self.region
+    }
+
+    fn set_region(&mut self, _: &Context, region: Rectangle) {
+        self.region = region;
+    }
+
+    fn is_focusable(&self) -> bool {
+        false
+    }
+
+    fn handle_input(&mut self, _: &mut Context, _: &Input) -> Response {
+        Response::Ignored
+    }
+}
+
+impl GraphicObject for Panel {
+    fn draw(&self, context: &mut Context) {
+        let translucent = match self.color {
+            Color::RGBA(_, _, _, alpha) => alpha < 255,
+            Color::RGB(..) => false,
+        };
+
+        if translucent {
+            context.sdl_renderer.set_blend_mode(BlendMode::Blend);
+        }
+
+        context.sdl_renderer.set_draw_color(self.color);
+        context.sdl_renderer.fill_rect(self.region.to_sdl_rectangle());
+
+        if translucent {
+            context.sdl_renderer.set_blend_mode(BlendMode::None);
+        }
+    }
+}
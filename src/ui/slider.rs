@@ -0,0 +1,171 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use context::Context;
+use graphics::{Align, Font, GraphicObject, Sprite};
+use input::Input;
+use math::{Rectangle, Vector2};
+use ui::{self, Response, Widget, WidgetEvent};
+
+/// Picks a number between min and max in steps, with left and right or by
+/// clicking on the track.  It starts at min.
+pub struct Slider {
+    label_sprite: Option<Sprite>,
+    region: Rectangle,
+    /// The right half of the region
+    track: Rectangle,
+
+    label: String,
+    font: Font,
+
+    min: f32,
+    max: f32,
+    step: f32,
+    value: f32,
+
+    focused: bool,
+}
+
+impl Slider {
+    pub fn new(context: &Context, font: &Font, label: &str, region: Rectangle, min: f32, max: f32, step: f32) -> Slider {
+        let mut slider = Slider {
+            label_sprite: None,
+            region: region,
+            track: region,
+
+            label: label.to_string(),
+            font: font.clone(),
+
+            min: min,
+            max: max,
+            step: step,
+            value: min,
+
+            focused: false,
+        };
+
+        slider.refresh(context);
+        slider
+    }
+
+    fn refresh(&mut self, context: &Context) {
+        let region = self.region;
+        let padding = context.rel.height(0.02);
+
+        self.label_sprite = ui::render_text(context, &self.font, &self.label, region, Align::Left);
+        self.track = Rectangle::new(
+            Vector2::new(
+                region.position.x + region.get_size().x / 2.0,
+                region.position.y + region.get_size().y * 0.35,
+            ),
+            Vector2::new(
+                (region.get_size().x / 2.0 - padding).max(0.0),
+                region.get_size().y * 0.3,
+            ),
+        ).unwrap();
+    }
+
+    pub fn get_value(&self) -> f32 {
+        self.value
+    }
+
+    /// Clamps and snaps value to a step
+    pub fn set_value(&mut self, value: f32) {
+        let value = if self.step > 0.0 {
+            self.min + ((value - self.min) / self.step).round() * self.step
+        } else {
+            value
+        };
+
+        self.value = value.max(self.min).min(self.max);
+    }
+
+    fn fraction(&self) -> f32 {
+        if self.max > self.min {
+            (self.value - self.min) / (self.max - self.min)
+        } else {
+            0.0
+        }
+    }
+
+    /// Sets the value and reports it if it changed
+    fn change(&mut self, value: f32) -> Response {
+        let old = self.value;
+        self.set_value(value);
+
+        if self.value != old {
+            Response::Event(WidgetEvent::Changed(self.value))
+        } else {
+            Response::Consumed
+        }
+    }
+}
+
+impl Widget for Slider {
+    fn get_region(&self) -> Rectangle {
+        self.region
+    }
+
+    fn set_region(&mut self, context: &Context, region: Rectangle) {
+        self.region = region;
+        self.refresh(context);
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn handle_input(&mut self, _: &mut Context, input: &Input) -> Response {
+        match *input {
+            Input::Left => {
+                let value = self.value - self.step;
+                self.change(value)
+            },
+            Input::Right => {
+                let value = self.value + self.step;
+                self.change(value)
+            },
+            Input::PointerDown(position) if position.x >= self.track.position.x => {
+                let fraction = (position.x - self.track.position.x) / self.track.get_size().x;
+                let value = self.min + fraction * (self.max - self.min);
+                self.change(value)
+            },
+            _ => Response::Ignored,
+        }
+    }
+}
+
+impl GraphicObject for Slider {
+    fn draw(&self, context: &mut Context) {
+        ui::draw_background(context, self.region, self.focused);
+
+        if let Some(ref label_sprite) = self.label_sprite {
+            label_sprite.draw(context);
+        }
+
+        context.sdl_renderer.set_draw_color(ui::WIDGET_DARK);
+        context.sdl_renderer.fill_rect(self.track.to_sdl_rectangle());
+
+        let mut filled = self.track;
+        filled.set_size(Vector2::new(self.track.get_size().x * self.fraction(), self.track.get_size().y));
+
+        context.sdl_renderer.set_draw_color(ui::WIDGET_TEXT);
+        context.sdl_renderer.fill_rect(filled.to_sdl_rectangle());
+    }
+}
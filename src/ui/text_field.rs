@@ -0,0 +1,211 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use context::Context;
+use graphics::{Align, Font, GraphicObject, Text};
+use input::Input;
+use math::{Rectangle, Vector2};
+use ui;
+
+const CARET_BLINK_NS: i64 = 500_000_000;
+
+/// A single line of editable text.  The view that owns it should call
+/// Context::start_text_input while it's being edited.
+pub struct TextField {
+    text: String,
+    /// In characters, not bytes
+    caret: usize,
+    max_length: usize,
+
+    font: Font,
+    region: Rectangle,
+
+    /// Drawn from the glyph atlas, so typing doesn't create textures
+    text_label: Text,
+    caret_offset: f32,
+    blink_ns: i64,
+}
+
+/// What happened to a text field during an update
+#[derive(PartialEq)]
+pub enum TextFieldEvent {
+    Committed,
+    Cancelled,
+}
+
+impl TextField {
+    pub fn new(context: &Context, font: &Font, region: Rectangle, max_length: usize) -> TextField {
+        let mut text_field = TextField {
+            text: String::new(),
+            caret: 0,
+            max_length: max_length,
+
+            font: font.clone(),
+            region: region,
+
+            text_label: Text::new(font, "", ui::WIDGET_TEXT),
+            caret_offset: 0.0,
+            blink_ns: 0,
+        };
+
+        text_field.refresh(context);
+        text_field
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn set_text(&mut self, context: &Context, text: &str) {
+        self.text = text.chars().take(self.max_length).collect();
+        self.caret = self.text.chars().count();
+        self.refresh(context);
+    }
+
+    /// Moves the field, e.g. after its layout is recomputed
+    pub fn set_region(&mut self, context: &Context, region: Rectangle) {
+        self.region = region;
+        self.refresh(context);
+    }
+
+    /// Applies a tick's text input
+    pub fn update(&mut self, context: &Context, input: &Vec<Input>) -> Option<TextFieldEvent> {
+        self.blink_ns = (self.blink_ns + context.clock.real_elapsed_ns()) % (2 * CARET_BLINK_NS);
+
+        let mut changed = false;
+        let mut event = None;
+
+        for i in input.iter() {
+            match *i {
+                Input::Text(ref text) => {
+                    for c in text.chars().filter(|c| !c.is_control()) {
+                        if self.text.chars().count() >= self.max_length {
+                            break;
+                        }
+
+                        let index = self.byte_index(self.caret);
+                        self.text.insert(index, c);
+                        self.caret += 1;
+                        changed = true;
+                    }
+                },
+                Input::Backspace => {
+                    if self.caret > 0 {
+                        let index = self.byte_index(self.caret - 1);
+                        self.text.remove(index);
+                        self.caret -= 1;
+                        changed = true;
+                    }
+                },
+                Input::Delete => {
+                    if self.caret < self.text.chars().count() {
+                        let index = self.byte_index(self.caret);
+                        self.text.remove(index);
+                        changed = true;
+                    }
+                },
+                Input::Home => {
+                    self.caret = 0;
+                    changed = true;
+                },
+                Input::End => {
+                    self.caret = self.text.chars().count();
+                    changed = true;
+                },
+                Input::Left => {
+                    if self.caret > 0 {
+                        self.caret -= 1;
+                        changed = true;
+                    }
+                },
+                Input::Right => {
+                    if self.caret < self.text.chars().count() {
+                        self.caret += 1;
+                        changed = true;
+                    }
+                },
+                Input::Commit => {
+                    event = Some(TextFieldEvent::Committed);
+                    break;
+                },
+                Input::Pause => {
+                    event = Some(TextFieldEvent::Cancelled);
+                    break;
+                },
+                _ => (),
+            }
+        }
+
+        if changed {
+            // Keep the caret solid while typing
+            self.blink_ns = 0;
+            self.refresh(context);
+        }
+
+        event
+    }
+
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.text.char_indices().nth(char_index).map(|(index, _)| index).unwrap_or(self.text.len())
+    }
+
+    fn refresh(&mut self, context: &Context) {
+        let padding = self.padding(context);
+        let region = self.region;
+
+        self.text_label.set_text(&self.text);
+        self.text_label.align = Align::Left;
+        self.text_label.position = Vector2::new(
+            region.position.x + padding,
+            region.position.y + region.get_size().y / 2.0,
+        );
+
+        let before_caret = &self.text[..self.byte_index(self.caret)];
+        self.caret_offset = context.font_renderer.size_of(&self.font, before_caret).map(|size| size.x).unwrap_or(0.0);
+    }
+
+    fn padding(&self, context: &Context) -> f32 {
+        context.rel.height(0.01)
+    }
+}
+
+impl GraphicObject for TextField {
+    fn draw(&self, context: &mut Context) {
+        context.sdl_renderer.set_draw_color(ui::WIDGET_DARK);
+        context.sdl_renderer.fill_rect(self.region.to_sdl_rectangle());
+
+        self.text_label.draw(context);
+
+        if self.blink_ns < CARET_BLINK_NS {
+            let caret = Rectangle::new(
+                Vector2::new(
+                    self.region.position.x + self.padding(context) + self.caret_offset,
+                    self.region.position.y + self.region.get_size().y * 0.15,
+                ),
+                Vector2::new(
+                    context.rel.height(0.004),
+                    self.region.get_size().y * 0.7,
+                ),
+            ).unwrap();
+
+            context.sdl_renderer.set_draw_color(ui::WIDGET_TEXT);
+            context.sdl_renderer.fill_rect(caret.to_sdl_rectangle());
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use context::Context;
+use graphics::{Align, Font, GraphicObject, Sprite};
+use input::Input;
+use math::Rectangle;
+use ui::{self, Response, Widget, WidgetEvent};
+
+/// An on/off switch, flipped by enter or left and right
+pub struct Toggle {
+    label_sprite: Option<Sprite>,
+    on_sprite: Option<Sprite>,
+    off_sprite: Option<Sprite>,
+    region: Rectangle,
+
+    label: String,
+    font: Font,
+
+    value: bool,
+    focused: bool,
+}
+
+impl Toggle {
+    pub fn new(context: &Context, font: &Font, label: &str, region: Rectangle, value: bool) -> Toggle {
+        let mut toggle = Toggle {
+            label_sprite: None,
+            on_sprite: None,
+            off_sprite: None,
+            region: region,
+
+            label: label.to_string(),
+            font: font.clone(),
+
+            value: value,
+            focused: false,
+        };
+
+        toggle.refresh(context);
+        toggle
+    }
+
+    fn refresh(&mut self, context: &Context) {
+        self.label_sprite = ui::render_text(context, &self.font, &self.label, self.region, Align::Left);
+        self.on_sprite = ui::render_text(context, &self.font, "On", self.region, Align::Right);
+        self.off_sprite = ui::render_text(context, &self.font, "Off", self.region, Align::Right);
+    }
+
+    pub fn get_value(&self) -> bool {
+        self.value
+    }
+
+    pub fn set_value(&mut self, value: bool) {
+        self.value = value;
+    }
+}
+
+impl Widget for Toggle {
+    fn get_region(&self) -> Rectangle {
+        self.region
+    }
+
+    fn set_region(&mut self, context: &Context, region: Rectangle) {
+        self.region = region;
+        self.refresh(context);
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn handle_input(&mut self, _: &mut Context, input: &Input) -> Response {
+        match *input {
+            Input::Enter | Input::Left | Input::Right => {
+                self.value = !self.value;
+                Response::Event(WidgetEvent::Toggled(self.value))
+            },
+            _ => Response::Ignored,
+        }
+    }
+}
+
+impl GraphicObject for Toggle {
+    fn draw(&self, context: &mut Context) {
+        ui::draw_background(context, self.region, self.focused);
+
+        if let Some(ref label_sprite) = self.label_sprite {
+            label_sprite.draw(context);
+        }
+
+        let value_sprite = if self.value { &self.on_sprite } else { &self.off_sprite };
+        if let Some(ref value_sprite) = *value_sprite {
+            value_sprite.draw(context);
+        }
+    }
+}
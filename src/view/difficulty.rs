@@ -0,0 +1,283 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use sdl2::pixels::Color;
+
+use context::Context;
+use graphics::{Align, Font, GraphicObject, Sprite, TextStyle};
+use input::{Input, InputState};
+use math::Rectangle;
+use ui::{Anchor, Button, Direction, Dropdown, Focus, Layout, Length, Slider, Slot, Toggle, Widget, WidgetEvent, SCREEN};
+use view::{Difficulty, Preset, Range, PRESETS, View, ViewAction, ViewData, ViewFlags};
+use view::{GAPS_RANGE, HAZARDS_RANGE, LIVES_RANGE, SPEED_RANGE, TIME_LIMIT_RANGE};
+
+/// Picks a difficulty preset, or sets each number by hand for Custom.  Changes
+/// are saved straight away and apply to the next run.
+pub struct DifficultyView {
+    title: Sprite,
+
+    preset: Dropdown,
+    speed: Slider,
+    gaps: Slider,
+    time_limit: Slider,
+    lives: Slider,
+    hazards: Slider,
+    adaptive: Toggle,
+    back: Button,
+
+    /// The numbers in words
+    summary: Option<Sprite>,
+
+    layout: Layout,
+    title_slot: Slot,
+    row_slots: Vec<Slot>,
+    summary_slot: Slot,
+
+    focus: Focus,
+    difficulty: Difficulty,
+
+    font: Font,
+}
+
+impl DifficultyView {
+    pub fn new(context: &mut Context) -> DifficultyView {
+        let title_font = context.font_renderer.load_font("assets/fonts/jim_teacher.ttf", context.rel.height(0.12) as u16);
+        let font = context.font_renderer.load_font("assets/fonts/fff_aquarius_bold.ttf", context.rel.height(0.04) as u16);
+
+        let title = match context.font_renderer.render_sprite(context, &title_font, "Difficulty", Color::RGB(255, 255, 255)) {
+            Some(sprite) => sprite,
+            None => panic!("Could not render difficulty title!"),
+        };
+
+        let difficulty = Difficulty::load(&context.config);
+
+        let mut layout = Layout::new();
+
+        let title_slot = layout.add(SCREEN, Anchor::Top, Length::Percent(100.0), Length::Percent(20.0));
+        let summary_slot = layout.add(SCREEN, Anchor::Bottom, Length::Percent(100.0), Length::Percent(16.0));
+
+        let list = layout.add(SCREEN, Anchor::Bottom, Length::Percent(60.0), Length::Percent(80.0));
+        layout.set_stack(list, Direction::Vertical, Length::ScreenHeight(1.2));
+
+        let row_slots = (0..8).map(|_| {
+            layout.add(list, Anchor::Top, Length::Percent(100.0), Length::ScreenHeight(7.0))
+        }).collect::<Vec<Slot>>();
+
+        layout.compute(context.screen_size);
+
+        let preset_names = PRESETS.iter().map(|preset| preset.name()).collect::<Vec<&str>>();
+        let selected = PRESETS.iter().position(|&preset| preset == difficulty.preset).unwrap_or(1);
+
+        let mut difficulty_view = DifficultyView {
+            title: title,
+
+            preset: Dropdown::new(context, &font, "Preset", layout.get(row_slots[0]), &preset_names, selected),
+            speed: slider(context, &font, "Lane Speed", layout.get(row_slots[1]), SPEED_RANGE),
+            gaps: slider(context, &font, "Log Gaps", layout.get(row_slots[2]), GAPS_RANGE),
+            time_limit: slider(context, &font, "Time Limit", layout.get(row_slots[3]), TIME_LIMIT_RANGE),
+            lives: slider(context, &font, "Lives", layout.get(row_slots[4]), LIVES_RANGE),
+            hazards: slider(context, &font, "Traffic", layout.get(row_slots[5]), HAZARDS_RANGE),
+            adaptive: Toggle::new(context, &font, "Adaptive", layout.get(row_slots[6]), difficulty.adaptive),
+            back: Button::new(context, &font, "Back", layout.get(row_slots[7])),
+
+            summary: None,
+
+            layout: layout,
+            title_slot: title_slot,
+            row_slots: row_slots,
+            summary_slot: summary_slot,
+
+            focus: Focus::new(),
+            difficulty: difficulty,
+
+            font: font,
+        };
+
+        difficulty_view.place(context);
+        difficulty_view.refresh(context);
+        difficulty_view
+    }
+
+    /// Moves everything to where the layout says it goes
+    fn place(&mut self, context: &Context) {
+        self.layout.compute(context.screen_size);
+
+        self.title.transform.position = self.layout.get(self.title_slot).get_center();
+
+        let regions = self.row_slots.iter().map(|&slot| self.layout.get(slot)).collect::<Vec<_>>();
+        self.preset.set_region(context, regions[0]);
+        self.speed.set_region(context, regions[1]);
+        self.gaps.set_region(context, regions[2]);
+        self.time_limit.set_region(context, regions[3]);
+        self.lives.set_region(context, regions[4]);
+        self.hazards.set_region(context, regions[5]);
+        self.adaptive.set_region(context, regions[6]);
+        self.back.set_region(context, regions[7]);
+
+        if let Some(ref mut summary) = self.summary {
+            summary.transform.position = self.layout.get(self.summary_slot).get_center();
+        }
+    }
+
+    /// Makes the widgets and summary show the current difficulty
+    fn refresh(&mut self, context: &Context) {
+        let difficulty = self.difficulty;
+
+        self.speed.set_value(difficulty.speed);
+        self.gaps.set_value(difficulty.gaps);
+        self.time_limit.set_value(difficulty.time_limit_s as f32);
+        self.lives.set_value(difficulty.lives as f32);
+        self.hazards.set_value(difficulty.hazards);
+
+        let text = format!(
+            "Speed x{:.2}, gaps x{:.2}, {} s, {} {}, traffic x{:.2}",
+            difficulty.speed,
+            difficulty.gaps,
+            difficulty.time_limit_s,
+            difficulty.lives,
+            if difficulty.lives == 1 { "life" } else { "lives" },
+            difficulty.hazards,
+        );
+
+        let position = self.layout.get(self.summary_slot).get_center();
+        self.summary = context.font_renderer.render_text(context, &self.font, &text, Color::RGB(255, 255, 255), &TextStyle::new(Align::Center)).map(|mut sprite| {
+            sprite.transform.position = position;
+            sprite
+        });
+    }
+
+    /// Any number changed by hand makes it a custom difficulty
+    fn customize(&mut self) {
+        if self.difficulty.preset != Preset::Custom {
+            self.difficulty.preset = Preset::Custom;
+            self.preset.set_selected(PRESETS.iter().position(|&preset| preset == Preset::Custom).unwrap());
+        }
+    }
+}
+
+impl View for DifficultyView {
+    fn get_name(&self) -> &'static str {
+        "DifficultyView"
+    }
+
+    fn get_view_data(&self) -> ViewData {
+        let mut graphic_objects = vec![
+            &self.title as &GraphicObject,
+
+            &self.speed,
+            &self.gaps,
+            &self.time_limit,
+            &self.lives,
+            &self.hazards,
+            &self.adaptive,
+            &self.back,
+        ];
+
+        if let Some(ref summary) = self.summary {
+            graphic_objects.push(summary);
+        }
+
+        // Last, so the open list goes over the rows under it
+        graphic_objects.push(&self.preset);
+
+        ViewData {
+            graphic_objects: graphic_objects,
+        }
+    }
+
+    fn update(&mut self, context: &mut Context, input: Vec<Input>, state: &InputState, _: i64) -> (Option<ViewAction>, Vec<Input>) {
+        let (event, unused) = self.focus.update(context, &mut vec![
+            &mut self.preset as &mut Widget,
+            &mut self.speed,
+            &mut self.gaps,
+            &mut self.time_limit,
+            &mut self.lives,
+            &mut self.hazards,
+            &mut self.adaptive,
+            &mut self.back,
+        ], &input, state);
+
+        if unused.iter().find(|input| **input == Input::Pause).is_some() {
+            return (Some(ViewAction::Return(Box::new(()))), Vec::new());
+        }
+
+        match event {
+            Some((0, WidgetEvent::Selected(index))) => {
+                let adaptive = self.difficulty.adaptive;
+                let preset = PRESETS[index];
+
+                // Custom picks up from whatever was set before
+                if preset == Preset::Custom {
+                    self.difficulty.preset = Preset::Custom;
+                } else {
+                    self.difficulty = Difficulty::preset(preset);
+                    self.difficulty.adaptive = adaptive;
+                }
+            },
+            Some((1, WidgetEvent::Changed(value))) => {
+                self.customize();
+                self.difficulty.speed = value;
+            },
+            Some((2, WidgetEvent::Changed(value))) => {
+                self.customize();
+                self.difficulty.gaps = value;
+            },
+            Some((3, WidgetEvent::Changed(value))) => {
+                self.customize();
+                self.difficulty.time_limit_s = value.round() as u32;
+            },
+            Some((4, WidgetEvent::Changed(value))) => {
+                self.customize();
+                self.difficulty.lives = value.round() as u32;
+            },
+            Some((5, WidgetEvent::Changed(value))) => {
+                self.customize();
+                self.difficulty.hazards = value;
+            },
+            Some((6, WidgetEvent::Toggled(value))) => {
+                self.difficulty.adaptive = value;
+            },
+            Some((7, WidgetEvent::Pressed)) => {
+                return (Some(ViewAction::Return(Box::new(()))), Vec::new());
+            },
+            _ => return (None, Vec::new()),
+        }
+
+        self.difficulty.save(&mut context.config);
+        context.save_config();
+        self.refresh(context);
+
+        (None, Vec::new())
+    }
+
+    fn get_flags(&self) -> ViewFlags {
+        ViewFlags {
+            blocks_input_below: true,
+            ..ViewFlags::default()
+        }
+    }
+
+    fn resize(&mut self, context: &mut Context) {
+        self.place(context);
+    }
+}
+
+fn slider(context: &Context, font: &Font, label: &str, region: Rectangle, range: Range) -> Slider {
+    Slider::new(context, font, label, region, range.min, range.max, range.step)
+}
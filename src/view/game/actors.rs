@@ -20,29 +20,171 @@
 use context::Context;
 use graphics::{GraphicObject, Sprite};
 use math::{Transform, Vector2};
+use save::SaveData;
 
-use view::game::GRID_CELL_SIZE;
+use view::game::{cell_position, GRID_CELL_SIZE, GRID_DIM};
+
+/// How long a hop from one cell to the next takes
+pub const HOP_NS: i64 = 120_000_000;
 
 pub struct Frog {
     sprite: Sprite,
+    previous_transform: Transform,
+
+    cell: Vector2,
+    /// The cell being hopped from and how far into the hop we are
+    hop: Option<(Vector2, i64)>,
+    /// The row at the top of the screen, for a scrolling grid
+    camera: f32,
 }
 
 impl Frog {
-    pub fn new(context: &Context) -> Frog {
-        Frog {
-            sprite: {
-                let mut sprite = Sprite::from_file(context, "assets/graphics/frog.png");
-                let scale = context.rel.height(GRID_CELL_SIZE.y) / sprite.get_region().get_size().y;
-                sprite.transform.scale = Vector2::new(scale, scale);
-                sprite
+    pub fn new(context: &Context, cell: Vector2) -> Frog {
+        let sprite = Sprite::from_file(context, "assets/graphics/frog.png");
+
+        let mut frog = Frog {
+            previous_transform: sprite.transform,
+            sprite: sprite,
+
+            cell: cell,
+            hop: None,
+            camera: 0.0,
+        };
+
+        frog.resize(context);
+        frog
+    }
+
+    /// Fits the frog to the grid again, e.g. after the window changes size
+    pub fn resize(&mut self, context: &Context) {
+        let scale = context.rel.height(GRID_CELL_SIZE.y) / self.sprite.get_region().get_size().y;
+        self.sprite.transform.scale = Vector2::new(scale, scale);
+
+        let position = self.get_position();
+        self.sprite.transform.position = self.screen_position(context, position);
+        self.previous_transform = self.sprite.transform;
+    }
+
+    pub fn get_cell(&self) -> Vector2 {
+        self.cell
+    }
+
+    pub fn is_hopping(&self) -> bool {
+        self.hop.is_some()
+    }
+
+    /// Scrolls the grid.  Takes effect from the next update.
+    pub fn set_camera(&mut self, camera: f32) {
+        self.camera = camera;
+    }
+
+    /// Starts a hop by offset cells, unless already hopping or it would leave
+    /// the part of the grid on screen
+    pub fn hop(&mut self, offset: Vector2) -> bool {
+        let target = self.cell + offset;
+
+        if self.hop.is_some() ||
+           target.x < 0.0 || target.x >= GRID_DIM.x ||
+           target.y < self.camera || target.y > self.camera + GRID_DIM.y - 1.0 {
+            return false;
+        }
+
+        self.hop = Some((self.cell, 0));
+        self.cell = target;
+        true
+    }
+
+    /// Moves the frog along with whatever it's sitting on
+    pub fn carry(&mut self, distance: f32) {
+        self.cell.x += distance;
+
+        if let Some((ref mut from, _)) = self.hop {
+            from.x += distance;
+        }
+    }
+
+    /// Puts the frog straight down in a cell, e.g. after it dies
+    pub fn respawn(&mut self, context: &Context, cell: Vector2) {
+        self.cell = cell;
+        self.hop = None;
+
+        self.sprite.transform.position = self.screen_position(context, cell);
+        self.previous_transform = self.sprite.transform;
+    }
+
+    pub fn save(&self, data: &mut SaveData) {
+        data.set("frog.x", self.cell.x);
+        data.set("frog.y", self.cell.y);
+        data.set("frog.hop", match self.hop {
+            Some((from, elapsed)) => format!("{} {} {}", from.x, from.y, elapsed),
+            None => "none".to_string(),
+        });
+    }
+
+    /// Picks up where a saved frog left off
+    pub fn load(&mut self, context: &Context, data: &SaveData) -> Result<(), String> {
+        let cell = Vector2::new(try!(data.get("frog.x")), try!(data.get("frog.y")));
+        if cell.x < -0.5 || cell.x > GRID_DIM.x - 0.5 || cell.y < 0.0 || cell.y >= GRID_DIM.y {
+            return Err("frog is off the grid".to_string());
+        }
+
+        let hop = match try!(data.get::<String>("frog.hop")).as_str() {
+            "none" => None,
+            hop => {
+                let pieces = hop.split_whitespace().collect::<Vec<&str>>();
+                if pieces.len() != 3 {
+                    return Err(format!("bad frog hop: {}", hop));
+                }
+
+                match (pieces[0].parse(), pieces[1].parse(), pieces[2].parse()) {
+                    (Ok(x), Ok(y), Ok(elapsed)) => Some((Vector2::new(x, y), elapsed)),
+                    _ => return Err(format!("bad frog hop: {}", hop)),
+                }
             },
+        };
+
+        self.respawn(context, cell);
+        self.hop = hop;
+        Ok(())
+    }
+
+    /// Advances the frog by one logic step.  Returns true if a hop finished.
+    pub fn update(&mut self, context: &Context, elapsed_ns: i64) -> bool {
+        self.previous_transform = self.sprite.transform;
+
+        let mut landed = false;
+        if let Some((from, elapsed)) = self.hop {
+            let elapsed = elapsed + elapsed_ns;
+
+            if elapsed >= HOP_NS {
+                self.hop = None;
+                landed = true;
+            } else {
+                self.hop = Some((from, elapsed));
+            }
         }
+
+        let position = self.get_position();
+        self.sprite.transform.position = self.screen_position(context, position);
+        landed
     }
-    
+
+    /// Where the frog is on the grid, part way between cells while hopping
+    fn get_position(&self) -> Vector2 {
+        match self.hop {
+            Some((from, elapsed)) => from.lerp(self.cell, elapsed as f32 / HOP_NS as f32),
+            None => self.cell,
+        }
+    }
+
+    fn screen_position(&self, context: &Context, cell: Vector2) -> Vector2 {
+        cell_position(context, Vector2::new(cell.x, cell.y - self.camera))
+    }
+
     pub fn get_transform(&self) -> &Transform {
         &self.sprite.transform
     }
-    
+
     pub fn get_transform_mut(&mut self) -> &mut Transform {
         &mut self.sprite.transform
     }
@@ -50,6 +192,8 @@ impl Frog {
 
 impl GraphicObject for Frog {
     fn draw(&self, context: &mut Context) {
-        self.sprite.draw(context);
+        let mut sprite = self.sprite.clone();
+        sprite.transform = self.previous_transform.lerp(&self.sprite.transform, context.interpolation);
+        sprite.draw(context);
     }
 }
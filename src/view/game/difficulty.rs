@@ -0,0 +1,190 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use config::Config;
+use save::SaveData;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Preset {
+    Easy,
+    Normal,
+    Hard,
+    /// Whatever the player set the numbers to
+    Custom,
+}
+
+pub const PRESETS: [Preset; 4] = [
+    Preset::Easy,
+    Preset::Normal,
+    Preset::Hard,
+    Preset::Custom,
+];
+
+impl Preset {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Preset::Easy => "Easy",
+            Preset::Normal => "Normal",
+            Preset::Hard => "Hard",
+            Preset::Custom => "Custom",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Preset> {
+        PRESETS.iter().find(|preset| preset.name().to_lowercase() == name.to_lowercase()).map(|&preset| preset)
+    }
+}
+
+/// What a custom number can be set to.  The difficulty sliders use these
+/// too, so a config can't hold anything they couldn't.
+#[derive(Copy, Clone, Debug)]
+pub struct Range {
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+impl Range {
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.max(self.min).min(self.max)
+    }
+}
+
+pub const SPEED_RANGE: Range = Range { min: 0.5, max: 2.0, step: 0.05 };
+pub const GAPS_RANGE: Range = Range { min: 0.5, max: 2.0, step: 0.05 };
+pub const TIME_LIMIT_RANGE: Range = Range { min: 10.0, max: 90.0, step: 5.0 };
+pub const LIVES_RANGE: Range = Range { min: 1.0, max: 9.0, step: 1.0 };
+pub const HAZARDS_RANGE: Range = Range { min: 0.5, max: 2.0, step: 0.05 };
+
+/// Everything about a run that the difficulty setting changes
+#[derive(Copy, Clone, Debug)]
+pub struct Difficulty {
+    pub preset: Preset,
+
+    /// Multiplies every lane's speed
+    pub speed: f32,
+    /// Multiplies the gaps between logs
+    pub gaps: f32,
+    pub time_limit_s: u32,
+    pub lives: u32,
+    /// Multiplies how many cars there are
+    pub hazards: f32,
+
+    /// Slows a lane down when the frog keeps dying in it
+    pub adaptive: bool,
+}
+
+impl Difficulty {
+    /// A preset's numbers.  Custom starts out the same as Normal.
+    pub fn preset(preset: Preset) -> Difficulty {
+        let (speed, gaps, time_limit_s, lives, hazards) = match preset {
+            Preset::Easy => (0.75, 0.75, 45, 5, 0.7),
+            Preset::Normal | Preset::Custom => (1.0, 1.0, 30, 3, 1.0),
+            Preset::Hard => (1.3, 1.3, 25, 2, 1.3),
+        };
+
+        Difficulty {
+            preset: preset,
+
+            speed: speed,
+            gaps: gaps,
+            time_limit_s: time_limit_s,
+            lives: lives,
+            hazards: hazards,
+
+            adaptive: false,
+        }
+    }
+
+    pub fn get_time_limit_ns(&self) -> i64 {
+        self.time_limit_s as i64 * 1_000_000_000
+    }
+
+    /// The player's setting
+    pub fn load(config: &Config) -> Difficulty {
+        let preset = config.get("difficulty").and_then(Preset::from_name).unwrap_or(Preset::Normal);
+        let mut difficulty = Difficulty::preset(preset);
+
+        if preset == Preset::Custom {
+            difficulty.speed = config.get_or("difficulty_speed", difficulty.speed);
+            difficulty.gaps = config.get_or("difficulty_gaps", difficulty.gaps);
+            difficulty.time_limit_s = config.get_or("difficulty_time_limit", difficulty.time_limit_s);
+            difficulty.lives = config.get_or("difficulty_lives", difficulty.lives);
+            difficulty.hazards = config.get_or("difficulty_hazards", difficulty.hazards);
+        }
+
+        difficulty.adaptive = config.get_or("adaptive_difficulty", false);
+        difficulty.clamped()
+    }
+
+    /// Brings hand edited numbers back into the ranges the sliders allow
+    fn clamped(mut self) -> Difficulty {
+        self.speed = SPEED_RANGE.clamp(self.speed);
+        self.gaps = GAPS_RANGE.clamp(self.gaps);
+        self.time_limit_s = TIME_LIMIT_RANGE.clamp(self.time_limit_s as f32) as u32;
+        self.lives = LIVES_RANGE.clamp(self.lives as f32) as u32;
+        self.hazards = HAZARDS_RANGE.clamp(self.hazards);
+        self
+    }
+
+    /// Custom numbers are kept even when a preset is picked, so going back
+    /// to Custom brings them back
+    pub fn save(&self, config: &mut Config) {
+        config.set("difficulty", self.preset.name().to_lowercase());
+
+        if self.preset == Preset::Custom {
+            config.set("difficulty_speed", self.speed);
+            config.set("difficulty_gaps", self.gaps);
+            config.set("difficulty_time_limit", self.time_limit_s);
+            config.set("difficulty_lives", self.lives);
+            config.set("difficulty_hazards", self.hazards);
+        }
+
+        config.set("adaptive_difficulty", self.adaptive);
+    }
+
+    /// For saving the run it's being played at
+    pub fn write(&self, data: &mut SaveData) {
+        data.set("difficulty", self.preset.name().to_lowercase());
+        data.set("difficulty.speed", self.speed);
+        data.set("difficulty.gaps", self.gaps);
+        data.set("difficulty.time_limit", self.time_limit_s);
+        data.set("difficulty.lives", self.lives);
+        data.set("difficulty.hazards", self.hazards);
+        data.set("difficulty.adaptive", self.adaptive);
+    }
+
+    pub fn read(data: &SaveData) -> Result<Difficulty, String> {
+        let preset = try!(data.get::<String>("difficulty"));
+
+        let difficulty = Difficulty {
+            preset: try!(Preset::from_name(&preset).ok_or(format!("unknown difficulty {}", preset))),
+
+            speed: try!(data.get("difficulty.speed")),
+            gaps: try!(data.get("difficulty.gaps")),
+            time_limit_s: try!(data.get("difficulty.time_limit")),
+            lives: try!(data.get("difficulty.lives")),
+            hazards: try!(data.get("difficulty.hazards")),
+
+            adaptive: try!(data.get("difficulty.adaptive")),
+        };
+
+        Ok(difficulty.clamped())
+    }
+}
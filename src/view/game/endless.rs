@@ -0,0 +1,485 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use std::any::Any;
+use std::collections::VecDeque;
+
+use context::Context;
+use graphics::GraphicObject;
+use input::{Input, InputState};
+use math::{Rectangle, Vector2};
+use random::{self, Rng};
+use view::{GameOverView, MainMenuView, PauseView, QuitToMenu, Restart, Transition, TransitionKind, View, ViewAction, ViewData};
+
+use view::game::{input_hop, Difficulty, GRID_DIM, GRID_SIZE};
+use view::game::actors::Frog;
+use view::game::hud::Hud;
+use view::game::level::{cell_rectangle, draw_lane, Lane, LaneKind};
+use view::game::terrain::{Ground, RIVER, ROAD};
+
+pub const DISTANCE_POINTS: u32 = 10;
+
+/// Over this many rows, blocks get longer, lanes faster and gaps smaller
+const RAMP_ROWS: f32 = 150.0;
+
+/// Road and river blocks are never longer than this, so the next safe row
+/// is always on screen above the last one
+const MAX_BLOCK_ROWS: i32 = 5;
+
+/// Cars never come closer together than this many cells, so a gap the frog
+/// fits through always comes by
+const MIN_ROAD_GAP: i32 = 2;
+const MAX_ROAD_SPEED: f32 = 4.0;
+
+/// Logs are always at least this long and never further apart than this,
+/// and neighbouring river lanes go opposite ways, so a log to hop onto
+/// comes by before the frog is carried off the grid
+const MIN_LOG_WIDTH: i32 = 2;
+const MAX_RIVER_GAP: i32 = 3;
+const MAX_RIVER_SPEED: f32 = 3.0;
+
+/// The camera keeps the last safe row the frog reached this far down the screen
+const CHECKPOINT_SCREEN_ROW: f32 = GRID_DIM.y - 2.0;
+const CAMERA_ROWS_PER_S: f32 = 6.0;
+
+/// An endless run of lanes going up from row 0, made as the camera reaches
+/// them.  Rows further up are more negative, to match the grid's
+/// coordinates.  The same seed and difficulty always make the same track.
+pub struct Track {
+    rng: Rng,
+    difficulty: Difficulty,
+
+    /// Top first, with the last one in bottom_row
+    lanes: VecDeque<Lane>,
+    bottom_row: i32,
+
+    /// The kind of the block being made and how many rows of it are left
+    block: Option<(LaneKind, i32)>,
+    /// Which way the last river lane went
+    river_direction: f32,
+
+    /// The row at the top of the screen, now and as of the previous update
+    camera: f32,
+    previous_camera: f32,
+}
+
+impl Track {
+    pub fn new(seed: u64, difficulty: Difficulty, camera: f32) -> Track {
+        let mut lanes = VecDeque::new();
+        lanes.push_back(Lane::safe());
+
+        let mut track = Track {
+            rng: Rng::new(seed),
+            difficulty: difficulty,
+
+            lanes: lanes,
+            bottom_row: 0,
+
+            block: None,
+            river_direction: 1.0,
+
+            camera: camera,
+            previous_camera: camera,
+        };
+
+        track.scroll_to(camera);
+        track
+    }
+
+    fn get_top_row(&self) -> i32 {
+        self.bottom_row - self.lanes.len() as i32 + 1
+    }
+
+    /// None for rows that haven't been made yet or have been forgotten
+    pub fn get_lane(&self, row: i32) -> Option<&Lane> {
+        let index = self.lanes.len() as i32 - 1 - (self.bottom_row - row);
+
+        if row > self.bottom_row || index < 0 {
+            None
+        } else {
+            self.lanes.get(index as usize)
+        }
+    }
+
+    pub fn get_camera(&self) -> f32 {
+        self.camera
+    }
+
+    /// Moves the camera, making lanes as they come into view and forgetting
+    /// the ones that have gone off the bottom
+    pub fn scroll_to(&mut self, camera: f32) {
+        self.previous_camera = self.camera;
+        self.camera = camera;
+
+        let top = camera.floor() as i32 - 1;
+        while self.get_top_row() > top {
+            let row = self.get_top_row() - 1;
+            let lane = self.make_lane(row);
+            self.lanes.push_front(lane);
+        }
+
+        let bottom = (camera + GRID_DIM.y).ceil() as i32 + 1;
+        while self.bottom_row > bottom && self.lanes.len() > 1 {
+            self.lanes.pop_back();
+            self.bottom_row -= 1;
+        }
+    }
+
+    /// Makes the lane for the row above the top one.  There's a safe row
+    /// before every block of road or river.
+    fn make_lane(&mut self, row: i32) -> Lane {
+        let ramp = ((-row) as f32 / RAMP_ROWS).max(0.0).min(1.0);
+
+        let (kind, rows_left) = match self.block {
+            Some(block) => block,
+            None => {
+                let longest = 2 + (ramp * (MAX_BLOCK_ROWS - 2) as f32).round() as i32;
+                let kind = if self.rng.chance(0.5) { LaneKind::Road } else { LaneKind::River };
+
+                self.block = Some((kind, self.rng.range(1, longest + 1)));
+                return Lane::safe();
+            },
+        };
+
+        self.block = if rows_left > 1 { Some((kind, rows_left - 1)) } else { None };
+
+        let speed_up = (1.0 + ramp) * self.difficulty.speed;
+
+        match kind {
+            LaneKind::River => {
+                self.river_direction = -self.river_direction;
+                let speed = self.river_direction * (self.rng.range_f32(0.8, 1.6) * speed_up).min(MAX_RIVER_SPEED);
+
+                let widest_log = (4 - ramp.round() as i32).max(MIN_LOG_WIDTH);
+                let widest_gap = ((2.0 + ramp) * self.difficulty.gaps).round() as i32;
+                let gaps = (1, widest_gap.max(1).min(MAX_RIVER_GAP));
+
+                Lane::generate(&mut self.rng, LaneKind::River, speed, (MIN_LOG_WIDTH, widest_log), gaps, 1.0)
+            },
+            _ => {
+                let direction = if row % 2 == 0 { 1.0 } else { -1.0 };
+                let speed = direction * (self.rng.range_f32(1.0, 2.5) * speed_up).min(MAX_ROAD_SPEED);
+
+                let widest_gap = ((6.0 - 3.0 * ramp) / self.difficulty.hazards).round() as i32;
+                let gaps = (MIN_ROAD_GAP, widest_gap.max(MIN_ROAD_GAP));
+
+                Lane::generate(&mut self.rng, LaneKind::Road, speed, (1, 2), gaps, 1.0)
+            },
+        }
+    }
+
+    pub fn update(&mut self, elapsed_ns: i64) {
+        for lane in self.lanes.iter_mut() {
+            lane.update(elapsed_ns);
+        }
+    }
+
+    /// How fast the lane under a frog in the given row carries it along
+    pub fn get_drift(&self, row: i32) -> f32 {
+        match self.get_lane(row) {
+            Some(lane) if lane.kind == LaneKind::River => lane.speed,
+            _ => 0.0,
+        }
+    }
+
+    pub fn is_safe(&self, row: i32) -> bool {
+        match self.get_lane(row) {
+            Some(lane) => lane.kind == LaneKind::Safe,
+            None => false,
+        }
+    }
+
+    /// Whether a frog sitting in the cell is hit by something or drowned
+    pub fn is_deadly(&self, cell: Vector2) -> bool {
+        if cell.x < -0.5 || cell.x > GRID_DIM.x - 0.5 {
+            return true;
+        }
+
+        match self.get_lane(cell.y as i32) {
+            Some(lane) => lane.is_deadly(cell.x),
+            None => false,
+        }
+    }
+}
+
+impl GraphicObject for Track {
+    fn draw(&self, context: &mut Context) {
+        let camera = self.previous_camera + (self.camera - self.previous_camera) * context.interpolation;
+        let first_row = self.get_top_row();
+
+        for (index, lane) in self.lanes.iter().enumerate() {
+            let color = match lane.kind {
+                LaneKind::Road => ROAD,
+                LaneKind::River => RIVER,
+                LaneKind::Safe | LaneKind::Homes => continue,
+            };
+
+            // Across the whole screen, like the fixed levels' road and river
+            let band = cell_rectangle(context, 0.0, (first_row + index as i32) as f32 - camera, GRID_DIM.x);
+            let band = Rectangle::new(Vector2::new(0.0, band.position.y), Vector2::new(context.screen_size.x, band.get_size().y)).unwrap();
+
+            context.sdl_renderer.set_draw_color(color);
+            context.sdl_renderer.fill_rect(band.to_sdl_rectangle());
+        }
+
+        // Objects wrapping around off the grid shouldn't show
+        let top_row = cell_rectangle(context, 0.0, 0.0, GRID_DIM.x);
+        let grid = Rectangle::new(top_row.position, Vector2::new(top_row.get_size().x, context.rel.height(GRID_SIZE.y))).unwrap();
+        context.sdl_renderer.set_clip_rect(Some(grid.to_sdl_rectangle()));
+
+        for (index, lane) in self.lanes.iter().enumerate() {
+            draw_lane(context, lane, (first_row + index as i32) as f32 - camera);
+        }
+
+        context.sdl_renderer.set_clip_rect(None);
+    }
+}
+
+/// Endless mode: the frog hops up a scrolling track that gets harder the
+/// further it goes.  Dying puts it back on the last safe row it reached.
+pub struct EndlessView {
+    ground: Ground,
+
+    track: Track,
+    frog: Frog,
+    hud: Hud,
+
+    lives: u32,
+    /// How many rows up from the start the frog has reached
+    distance: u32,
+    /// The last safe row the frog reached, where it starts over after dying
+    checkpoint: i32,
+    high_score: u32,
+
+    /// Set when a menu over the game says to leave or start over
+    quit_pending: bool,
+    restart_pending: bool,
+}
+
+impl EndlessView {
+    /// The track is made from the endless_seed config key if there is one,
+    /// so a run can be played again, or else the gameplay stream
+    pub fn new(context: &mut Context) -> EndlessView {
+        let difficulty = Difficulty::load(&context.config);
+
+        let seed = match context.config.get("endless_seed").and_then(|seed| seed.parse().ok()) {
+            Some(seed) => seed,
+            None => context.random.stream(random::GAMEPLAY).next_u64(),
+        };
+        info!("Endless run seed {}", seed);
+
+        let camera = camera_for(0);
+        let mut frog = Frog::new(context, start_cell());
+        frog.set_camera(camera);
+        frog.respawn(context, start_cell());
+
+        let mut endless_view = EndlessView {
+            ground: Ground::new(),
+
+            track: Track::new(seed, difficulty, camera),
+            frog: frog,
+            hud: Hud::new(context),
+
+            lives: difficulty.lives,
+            distance: 0,
+            checkpoint: 0,
+            high_score: context.config.get_or("endless_high_score", 0),
+
+            quit_pending: false,
+            restart_pending: false,
+        };
+
+        endless_view.refresh_hud(context);
+        endless_view
+    }
+
+    fn get_score(&self) -> u32 {
+        self.distance * DISTANCE_POINTS
+    }
+
+    fn refresh_hud(&mut self, context: &Context) {
+        let score = self.get_score();
+        self.hud.set(context, "Distance", self.distance, score, self.lives, None);
+    }
+
+    fn die(&mut self, context: &mut Context) -> Option<ViewAction> {
+        self.lives -= 1;
+        debug!("Frog died in row {}, {} lives left", self.frog.get_cell().y, self.lives);
+
+        if self.lives == 0 {
+            let breakdown = vec![(format!("Distance x{}", self.distance), self.get_score())];
+            let game_over = GameOverView::new(context, "Game Over", breakdown, "endless_high_score", self.high_score);
+            return Some(ViewAction::Push(Box::new(game_over)));
+        }
+
+        let cell = Vector2::new(start_cell().x, self.checkpoint as f32);
+        self.frog.respawn(context, cell);
+        None
+    }
+
+    /// Moves the camera toward the checkpoint, never back down
+    fn scroll(&mut self, elapsed_ns: i64) {
+        let target = camera_for(self.checkpoint);
+        let camera = self.track.get_camera();
+
+        let camera = if camera > target {
+            (camera - CAMERA_ROWS_PER_S * elapsed_ns as f32 / 1_000_000_000.0).max(target)
+        } else {
+            camera
+        };
+
+        self.track.scroll_to(camera);
+        self.frog.set_camera(camera);
+    }
+}
+
+/// Where the camera sits with the checkpoint in the given row
+fn camera_for(checkpoint: i32) -> f32 {
+    checkpoint as f32 - CHECKPOINT_SCREEN_ROW
+}
+
+fn start_cell() -> Vector2 {
+    Vector2::new((GRID_DIM.x / 2.0).floor(), 0.0)
+}
+
+impl View for EndlessView {
+    fn get_name(&self) -> &'static str {
+        "EndlessView"
+    }
+
+    fn get_view_data(&self) -> ViewData {
+        ViewData {
+            graphic_objects: vec![
+                &self.ground as &GraphicObject,
+                &self.track,
+                &self.frog,
+                &self.hud,
+            ],
+        }
+    }
+
+    fn update(&mut self, context: &mut Context, input: Vec<Input>, _: &InputState, elapsed_ns: i64) -> (Option<ViewAction>, Vec<Input>) {
+        if self.restart_pending {
+            let endless_view = Box::new(EndlessView::new(context));
+            return (Some(ViewAction::TransitionTo(endless_view, Transition::new(TransitionKind::Fade, 600))), Vec::new());
+        }
+
+        if self.quit_pending {
+            let main_menu = Box::new(MainMenuView::new(context));
+            return (Some(ViewAction::TransitionTo(main_menu, Transition::new(TransitionKind::Slide, 400))), Vec::new());
+        }
+
+        // The game over screen is up
+        if self.lives == 0 {
+            return (None, Vec::new());
+        }
+
+        if input.iter().find(|input| **input == Input::Pause || **input == Input::FocusLost).is_some() {
+            return (Some(ViewAction::Push(Box::new(PauseView::new(context)))), Vec::new());
+        }
+
+        for i in input.iter() {
+            let offset = match input_hop(context, &self.frog, i) {
+                Some(offset) => offset,
+                None => continue,
+            };
+
+            // Off the river, the frog lines back up with the columns
+            let cell = self.frog.get_cell();
+            let offset = if self.track.get_drift((cell.y + offset.y) as i32) == 0.0 {
+                Vector2::new(offset.x + cell.x.round() - cell.x, offset.y)
+            } else {
+                offset
+            };
+
+            if self.frog.hop(offset) {
+                break;
+            }
+        }
+
+        self.track.update(elapsed_ns);
+
+        let drift = self.track.get_drift(self.frog.get_cell().y as i32);
+        self.frog.carry(drift * elapsed_ns as f32 / 1_000_000_000.0);
+
+        self.scroll(elapsed_ns);
+        let landed = self.frog.update(context, elapsed_ns);
+
+        let view_action = if (landed || !self.frog.is_hopping()) && self.track.is_deadly(self.frog.get_cell()) {
+            self.die(context)
+        } else {
+            if landed {
+                let row = self.frog.get_cell().y as i32;
+
+                self.distance = self.distance.max((-row).max(0) as u32);
+                if row < self.checkpoint && self.track.is_safe(row) {
+                    self.checkpoint = row;
+                }
+            }
+            None
+        };
+
+        self.refresh_hud(context);
+
+        (view_action, Vec::new())
+    }
+
+    fn resize(&mut self, context: &mut Context) {
+        self.frog.resize(context);
+        self.hud.place(context);
+    }
+
+    fn child_returned(&mut self, value: Box<Any>) {
+        if value.is::<Restart>() {
+            self.restart_pending = true;
+        } else if value.is::<QuitToMenu>() {
+            self.quit_pending = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use view::game::{Difficulty, GRID_DIM, PRESETS};
+    use view::game::solver::check_block;
+    use super::Track;
+
+    const SEED: u64 = 0x5eed;
+    const ROWS: i32 = 300;
+    /// Far more than any block should need
+    const BLOCK_TIME_LIMIT_NS: i64 = 30_000_000_000;
+
+    #[test]
+    fn every_block_can_be_crossed() {
+        for &preset in PRESETS.iter() {
+            let mut track = Track::new(SEED, Difficulty::preset(preset), 0.0);
+            let mut row = 0;
+
+            while -row < ROWS {
+                track.scroll_to(row as f32 - GRID_DIM.y);
+
+                let next = (1..GRID_DIM.y as i32).map(|up| row - up).find(|&row| track.is_safe(row));
+                assert!(next.is_some(), "no safe row above row {} on {}", row, preset.name());
+                assert!(check_block(&track, row, BLOCK_TIME_LIMIT_NS).is_some(), "can't get past row {} on {}", row, preset.name());
+
+                row = next.unwrap();
+            }
+        }
+    }
+}
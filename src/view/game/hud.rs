@@ -0,0 +1,143 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use sdl2::pixels::Color;
+
+use context::Context;
+use graphics::{Align, Font, GraphicObject, Sprite, Text, TextStyle};
+use math::{Rectangle, Vector2};
+
+use view::game::GRID_SIZE;
+
+const HUD_TEXT: Color = Color::RGB(255, 255, 255);
+const TIME_BAR: Color = Color::RGB(230, 200, 40);
+const TIME_BAR_LOW: Color = Color::RGB(220, 40, 40);
+const TIME_BAR_BACK: Color = Color::RGB(30, 60, 0);
+
+/// The level (or whatever the mode counts instead), score, lives and time
+/// left, down the left of the grid
+pub struct Hud {
+    level: Text,
+    score: Text,
+    /// Markup, re-rendered only when the count changes
+    lives: Option<Sprite>,
+    shown_lives: Option<u32>,
+    font: Font,
+
+    /// From 0 to 1, or None when there's no timer
+    time_left: Option<f32>,
+    time_bar: Rectangle,
+}
+
+impl Hud {
+    pub fn new(context: &mut Context) -> Hud {
+        // Only has capitals, digits and some punctuation
+        let font = context.font_renderer.load_font("assets/fonts/pixel.fnt", context.rel.height(0.04) as u16);
+        let bold_font = context.font_renderer.load_font("assets/fonts/pixel_bold.fnt", context.rel.height(0.04) as u16);
+        context.font_renderer.set_bold(&font, &bold_font);
+        context.font_renderer.load_icon("life", "assets/graphics/life.png");
+
+        let mut hud = Hud {
+            level: hud_text(&font),
+            score: hud_text(&font),
+            lives: None,
+            shown_lives: None,
+            font: font,
+
+            time_left: Some(1.0),
+            time_bar: Rectangle::new(Vector2::zero(), Vector2::new(1.0, 1.0)).unwrap(),
+        };
+
+        hud.place(context);
+        hud
+    }
+
+    /// Lines everything up in the space left of the grid
+    pub fn place(&mut self, context: &Context) {
+        let left = context.rel.height(0.02);
+        let line = context.rel.height(0.06);
+
+        self.level.position = Vector2::new(left, line);
+        self.score.position = Vector2::new(left, line * 2.0);
+        if let Some(ref mut lives) = self.lives {
+            lives.transform.position = Vector2::new(left + lives.get_region().get_size().x / 2.0, line * 3.0);
+        }
+
+        let width = context.rel.center_width(context.rel.height(GRID_SIZE.x)) - left * 2.0;
+        self.time_bar = Rectangle::new(
+            Vector2::new(left, line * 3.7),
+            Vector2::new(width.max(1.0), context.rel.height(0.025)),
+        ).unwrap();
+    }
+
+    /// The first line reads e.g. "LEVEL 3" from the label and number
+    pub fn set(&mut self, context: &Context, label: &str, number: u32, score: u32, lives: u32, time_left: Option<f32>) {
+        // Only touch the text when it changes, since the timer moves every step
+        let level = format!("{} {}", label.to_uppercase(), number);
+        if self.level.get_text() != level {
+            self.level.set_text(&level);
+        }
+
+        let score = format!("SCORE {}", score);
+        if self.score.get_text() != score {
+            self.score.set_text(&score);
+        }
+
+        if self.shown_lives != Some(lives) {
+            let markup = format!("[icon=life] [b]{}[/b]", lives);
+            self.lives = context.font_renderer.render_markup(context, &self.font, &markup, HUD_TEXT, &TextStyle::new(Align::Left));
+            self.shown_lives = Some(lives);
+            self.place(context);
+        }
+
+        self.time_left = time_left.map(|time_left| time_left.max(0.0).min(1.0));
+    }
+}
+
+fn hud_text(font: &Font) -> Text {
+    let mut text = Text::new(font, "", HUD_TEXT);
+    text.align = Align::Left;
+    text
+}
+
+impl GraphicObject for Hud {
+    fn draw(&self, context: &mut Context) {
+        self.level.draw(context);
+        self.score.draw(context);
+        if let Some(ref lives) = self.lives {
+            lives.draw(context);
+        }
+
+        let time_left = match self.time_left {
+            Some(time_left) => time_left,
+            None => return,
+        };
+
+        context.sdl_renderer.set_draw_color(TIME_BAR_BACK);
+        context.sdl_renderer.fill_rect(self.time_bar.to_sdl_rectangle());
+
+        let mut remaining = self.time_bar.get_size();
+        remaining.x *= time_left;
+
+        if remaining.x >= 1.0 {
+            context.sdl_renderer.set_draw_color(if time_left < 0.25 { TIME_BAR_LOW } else { TIME_BAR });
+            context.sdl_renderer.fill_rect(Rectangle::new(self.time_bar.position, remaining).unwrap().to_sdl_rectangle());
+        }
+    }
+}
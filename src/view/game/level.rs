@@ -0,0 +1,535 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use sdl2::pixels::Color;
+
+use context::Context;
+use graphics::GraphicObject;
+use math::{Rectangle, Vector2};
+use random::Rng;
+use save::SaveData;
+
+use view::game::{cell_position, Difficulty, GRID_CELL_SIZE, GRID_DIM, GRID_SIZE};
+
+/// Lane objects wrap around over the grid plus this many cells, so they can
+/// drive fully off one side before coming back on the other
+const WRAP_MARGIN: f32 = 4.0;
+
+pub const HOME_ROW: usize = 0;
+const MEDIAN_ROW: usize = 6;
+pub const START_ROW: usize = 12;
+
+const HOME_COLUMNS: [f32; 5] = [1.0, 4.0, 7.0, 10.0, 13.0];
+
+/// How long a fly sits in a home, and how long between flies
+const FLY_NS: i64 = 4_000_000_000;
+const FLY_COOLDOWN_NS: (i32, i32) = (3_000, 8_000);
+
+/// How much the adaptive difficulty slows a lane each time, and how many times
+const EASE_FACTOR: f32 = 0.8;
+const MAX_EASES: u32 = 3;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum LaneKind {
+    Safe,
+    /// The frog dies touching anything in it
+    Road,
+    /// The frog dies touching nothing in it
+    River,
+    Homes,
+}
+
+impl LaneKind {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            LaneKind::Safe => "safe",
+            LaneKind::Road => "road",
+            LaneKind::River => "river",
+            LaneKind::Homes => "homes",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LaneKind> {
+        match name {
+            "safe" => Some(LaneKind::Safe),
+            "road" => Some(LaneKind::Road),
+            "river" => Some(LaneKind::River),
+            "homes" => Some(LaneKind::Homes),
+            _ => None,
+        }
+    }
+}
+
+/// A car or a log, in cells along the lane's loop
+#[derive(Copy, Clone, Debug)]
+pub struct LaneObject {
+    pub x: f32,
+    pub width: f32,
+}
+
+/// One row of the grid.  Everything in it moves together, wrapping around.
+#[derive(Clone, Debug)]
+pub struct Lane {
+    pub kind: LaneKind,
+    /// Cells per second; negative moves left
+    pub speed: f32,
+    pub objects: Vec<LaneObject>,
+
+    /// How far the objects have moved, wrapped to the loop's length
+    pub offset: f32,
+    previous_offset: f32,
+}
+
+impl Lane {
+    pub fn new(kind: LaneKind, speed: f32, objects: Vec<LaneObject>) -> Lane {
+        Lane {
+            kind: kind,
+            speed: speed,
+            objects: objects,
+
+            offset: 0.0,
+            previous_offset: 0.0,
+        }
+    }
+
+    pub fn safe() -> Lane {
+        Lane::new(LaneKind::Safe, 0.0, Vec::new())
+    }
+
+    /// Fills a lane with objects of random widths and gaps.  Gaps are scaled,
+    /// but always at least a cell.
+    pub fn generate(rng: &mut Rng, kind: LaneKind, speed: f32, widths: (i32, i32), gaps: (i32, i32), gap_scale: f32) -> Lane {
+        let gaps = (
+            ((gaps.0 as f32 * gap_scale).round() as i32).max(1),
+            ((gaps.1 as f32 * gap_scale).round() as i32).max(1),
+        );
+        let gaps = (gaps.0, gaps.1.max(gaps.0));
+
+        let length = loop_length();
+        let mut objects = Vec::new();
+        let mut x = rng.range_f32(0.0, gaps.1 as f32);
+
+        loop {
+            let width = rng.range(widths.0, widths.1 + 1) as f32;
+
+            // Leave at least the smallest gap before the first object comes around again
+            if x + width + gaps.0 as f32 > length {
+                break;
+            }
+
+            objects.push(LaneObject {
+                x: x,
+                width: width,
+            });
+            x += width + rng.range(gaps.0, gaps.1 + 1) as f32;
+        }
+
+        Lane::new(kind, speed, objects)
+    }
+
+    /// The kind, speed, offset, then each object as x:width
+    fn to_save_string(&self) -> String {
+        let mut pieces = vec![self.kind.name().to_string(), self.speed.to_string(), self.offset.to_string()];
+        pieces.extend(self.objects.iter().map(|object| format!("{}:{}", object.x, object.width)));
+        pieces.join(" ")
+    }
+
+    fn from_save_string(value: &str) -> Option<Lane> {
+        let mut pieces = value.split_whitespace();
+
+        let kind = match pieces.next().and_then(LaneKind::from_name) {
+            Some(kind) => kind,
+            None => return None,
+        };
+        let speed = match pieces.next().and_then(|piece| piece.parse().ok()) {
+            Some(speed) => speed,
+            None => return None,
+        };
+        let offset = match pieces.next().and_then(|piece| piece.parse().ok()) {
+            Some(offset) => offset,
+            None => return None,
+        };
+
+        let mut objects = Vec::new();
+        for piece in pieces {
+            let mut numbers = piece.splitn(2, ':').map(|number| number.parse::<f32>().ok());
+            match (numbers.next(), numbers.next()) {
+                (Some(Some(x)), Some(Some(width))) => objects.push(LaneObject {
+                    x: x,
+                    width: width,
+                }),
+                _ => return None,
+            }
+        }
+
+        let mut lane = Lane::new(kind, speed, objects);
+        lane.offset = offset;
+        lane.previous_offset = offset;
+        Some(lane)
+    }
+
+    pub fn update(&mut self, elapsed_ns: i64) {
+        self.previous_offset = self.offset;
+        self.offset = wrap(self.offset + self.speed * elapsed_ns as f32 / 1_000_000_000.0, loop_length());
+    }
+
+    /// Whether any object covers part of the span, in grid cells
+    pub fn overlaps(&self, left: f32, right: f32) -> bool {
+        self.objects.iter().any(|object| {
+            let start = wrap(left + WRAP_MARGIN / 2.0 - object.x - self.offset, loop_length());
+            start < object.width || start + (right - left) > loop_length()
+        })
+    }
+
+    /// Whether a frog at column x in this lane is hit by something or drowned
+    pub fn is_deadly(&self, x: f32) -> bool {
+        match self.kind {
+            LaneKind::Road => self.overlaps(x + 0.15, x + 0.85),
+            LaneKind::River => !self.covers(x + 0.5),
+            LaneKind::Safe | LaneKind::Homes => false,
+        }
+    }
+
+    /// Whether an object is under the point, in grid cells
+    pub fn covers(&self, x: f32) -> bool {
+        self.objects.iter().any(|object| {
+            wrap(x + WRAP_MARGIN / 2.0 - object.x - self.offset, loop_length()) < object.width
+        })
+    }
+
+    /// Where each object is on the grid, as (left, width), with the offset
+    /// interpolated since the last update.  Objects crossing the wrap point
+    /// are returned at both ends.
+    pub fn get_spans(&self, interpolation: f32) -> Vec<(f32, f32)> {
+        let length = loop_length();
+
+        let mut moved = self.offset - self.previous_offset;
+        if moved.abs() > length / 2.0 {
+            moved -= length * moved.signum();
+        }
+        let offset = self.previous_offset + moved * interpolation;
+
+        let mut spans = Vec::new();
+        for object in self.objects.iter() {
+            let left = wrap(object.x + offset, length) - WRAP_MARGIN / 2.0;
+            spans.push((left, object.width));
+            spans.push((left - length, object.width));
+        }
+        spans
+    }
+}
+
+fn loop_length() -> f32 {
+    GRID_DIM.x + WRAP_MARGIN
+}
+
+fn wrap(value: f32, length: f32) -> f32 {
+    let value = value % length;
+    if value < 0.0 { value + length } else { value }
+}
+
+/// A bay in the top row for the frog to reach
+#[derive(Copy, Clone, Debug)]
+pub struct Home {
+    pub column: f32,
+    pub filled: bool,
+}
+
+/// Where the frog ended up after landing somewhere
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Landing {
+    Safe,
+    Died,
+    /// Reached an empty home, and whether a fly was in it
+    Home(bool),
+}
+
+/// The lanes and homes of one level.  Nothing in here needs SDL, so levels
+/// can be simulated without a window.
+#[derive(Clone)]
+pub struct Level {
+    pub number: u32,
+    /// One per row, top first
+    pub lanes: Vec<Lane>,
+    pub homes: Vec<Home>,
+    pub time_limit_ns: i64,
+
+    /// Which home a fly is in and for how long yet, or how long until one
+    /// appears
+    pub fly: Option<(usize, i64)>,
+    pub fly_cooldown_ns: i64,
+}
+
+impl Level {
+    /// Lanes get faster and more crowded with each level
+    pub fn new(number: u32, difficulty: &Difficulty, rng: &mut Rng) -> Level {
+        let speed_up = (1.0 + 0.15 * (number.max(1) - 1) as f32) * difficulty.speed;
+        let crowding = ((number.max(1) - 1) / 2).min(2) as i32;
+
+        let mut lanes = vec![Lane::new(LaneKind::Homes, 0.0, Vec::new())];
+
+        for row in 1..MEDIAN_ROW {
+            let direction = if row % 2 == 0 { 1.0 } else { -1.0 };
+            let speed = direction * rng.range_f32(0.8, 1.8) * speed_up;
+            lanes.push(Lane::generate(rng, LaneKind::River, speed, (2, 4), (2, 4 - crowding / 2), difficulty.gaps));
+        }
+
+        lanes.push(Lane::safe());
+
+        for row in (MEDIAN_ROW + 1)..START_ROW {
+            let direction = if row % 2 == 0 { -1.0 } else { 1.0 };
+            let speed = direction * rng.range_f32(1.0, 2.5) * speed_up;
+            lanes.push(Lane::generate(rng, LaneKind::Road, speed, (1, 2), (3, 6 - crowding), 1.0 / difficulty.hazards));
+        }
+
+        lanes.push(Lane::safe());
+
+        Level {
+            number: number,
+            lanes: lanes,
+            homes: HOME_COLUMNS.iter().map(|&column| Home {
+                column: column,
+                filled: false,
+            }).collect(),
+            time_limit_ns: difficulty.get_time_limit_ns(),
+
+            fly: None,
+            fly_cooldown_ns: FLY_COOLDOWN_NS.0 as i64 * 1_000_000,
+        }
+    }
+
+    pub fn save(&self, data: &mut SaveData) {
+        data.set("level", self.number);
+        data.set("level.time_limit_ns", self.time_limit_ns);
+        data.set("level.homes", self.homes.iter().map(|home| if home.filled { '1' } else { '0' }).collect::<String>());
+        data.set("level.fly", match self.fly {
+            Some((home, left_ns)) => format!("{} {}", home, left_ns),
+            None => "none".to_string(),
+        });
+        data.set("level.fly_cooldown_ns", self.fly_cooldown_ns);
+
+        data.set("level.lanes", self.lanes.len());
+        for (row, lane) in self.lanes.iter().enumerate() {
+            data.set(&format!("lane.{}", row), lane.to_save_string());
+        }
+    }
+
+    pub fn load(data: &SaveData) -> Result<Level, String> {
+        let homes = try!(data.get::<String>("level.homes"));
+        if homes.len() != HOME_COLUMNS.len() {
+            return Err("wrong number of homes".to_string());
+        }
+
+        let fly = match try!(data.get::<String>("level.fly")).as_str() {
+            "none" => None,
+            fly => {
+                let mut numbers = fly.split_whitespace();
+                match (numbers.next().and_then(|n| n.parse().ok()), numbers.next().and_then(|n| n.parse().ok())) {
+                    (Some(home), Some(left_ns)) if home < HOME_COLUMNS.len() => Some((home, left_ns)),
+                    _ => return Err(format!("bad fly: {}", fly)),
+                }
+            },
+        };
+
+        let mut lanes = Vec::new();
+        for row in 0..try!(data.get::<usize>("level.lanes")) {
+            let key = format!("lane.{}", row);
+            match Lane::from_save_string(&try!(data.get::<String>(&key))) {
+                Some(lane) => lanes.push(lane),
+                None => return Err(format!("bad {}", key)),
+            }
+        }
+
+        if lanes.len() != GRID_DIM.y as usize {
+            return Err("wrong number of lanes".to_string());
+        }
+
+        Ok(Level {
+            number: try!(data.get("level")),
+            lanes: lanes,
+            homes: HOME_COLUMNS.iter().zip(homes.chars()).map(|(&column, filled)| Home {
+                column: column,
+                filled: filled == '1',
+            }).collect(),
+            time_limit_ns: try!(data.get("level.time_limit_ns")),
+
+            fly: fly,
+            fly_cooldown_ns: try!(data.get("level.fly_cooldown_ns")),
+        })
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.homes.iter().all(|home| home.filled)
+    }
+
+    pub fn update(&mut self, rng: &mut Rng, elapsed_ns: i64) {
+        for lane in self.lanes.iter_mut() {
+            lane.update(elapsed_ns);
+        }
+
+        self.fly = match self.fly {
+            Some((home, left_ns)) if left_ns > elapsed_ns && !self.homes[home].filled => Some((home, left_ns - elapsed_ns)),
+            Some(_) => {
+                self.fly_cooldown_ns = rng.range(FLY_COOLDOWN_NS.0, FLY_COOLDOWN_NS.1) as i64 * 1_000_000;
+                None
+            },
+            None => {
+                self.fly_cooldown_ns -= elapsed_ns;
+
+                let empty = self.homes.iter().enumerate()
+                    .filter(|&(_, home)| !home.filled)
+                    .map(|(index, _)| index)
+                    .collect::<Vec<usize>>();
+
+                if self.fly_cooldown_ns <= 0 && !empty.is_empty() {
+                    Some((empty[rng.range(0, empty.len() as i32) as usize], FLY_NS))
+                } else {
+                    None
+                }
+            },
+        };
+    }
+
+    /// Slows a lane down, for the adaptive difficulty.  Returns false once
+    /// it's been slowed as much as it will be.
+    pub fn ease_lane(&mut self, row: usize, times_eased: u32) -> bool {
+        if times_eased >= MAX_EASES {
+            return false;
+        }
+
+        match self.lanes.get_mut(row) {
+            Some(lane) if lane.speed != 0.0 => {
+                lane.speed *= EASE_FACTOR;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// How fast the lane under a frog in the given row carries it along
+    pub fn get_drift(&self, row: usize) -> f32 {
+        match self.lanes.get(row) {
+            Some(lane) if lane.kind == LaneKind::River => lane.speed,
+            _ => 0.0,
+        }
+    }
+
+    /// Whether a frog sitting in the cell is hit by something or drowned
+    pub fn is_deadly(&self, cell: Vector2) -> bool {
+        if cell.x < -0.5 || cell.x > GRID_DIM.x - 0.5 {
+            return true;
+        }
+
+        match self.lanes.get(cell.y as usize) {
+            Some(lane) => lane.is_deadly(cell.x),
+            None => false,
+        }
+    }
+
+    /// Called when a hop finishes.  Fills the home the frog landed in, if any.
+    pub fn land(&mut self, cell: Vector2) -> Landing {
+        if cell.y as usize != HOME_ROW {
+            return if self.is_deadly(cell) { Landing::Died } else { Landing::Safe };
+        }
+
+        let index = self.homes.iter().position(|home| (home.column - cell.x).abs() < 0.5);
+        match index {
+            Some(index) if !self.homes[index].filled => {
+                self.homes[index].filled = true;
+
+                let fly = match self.fly {
+                    Some((home, _)) => home == index,
+                    None => false,
+                };
+                Landing::Home(fly)
+            },
+            _ => Landing::Died,
+        }
+    }
+}
+
+const CAR: Color = Color::RGB(200, 40, 40);
+const LOG: Color = Color::RGB(120, 70, 20);
+const HEDGE: Color = Color::RGB(20, 90, 20);
+const BAY: Color = Color::RGB(0, 0, 90);
+const FILLED: Color = Color::RGB(100, 220, 60);
+const FLY: Color = Color::RGB(20, 20, 20);
+
+/// The screen region of a run of cells.  The row can be fractional, for a
+/// scrolling grid.
+pub fn cell_rectangle(context: &Context, left: f32, row: f32, width: f32) -> Rectangle {
+    let cell_size = Vector2::new(context.rel.height(GRID_CELL_SIZE.x), context.rel.height(GRID_CELL_SIZE.y));
+    let center = cell_position(context, Vector2::new(left, row));
+
+    Rectangle::new(
+        center - cell_size / 2.0,
+        Vector2::new(cell_size.x * width, cell_size.y),
+    ).unwrap()
+}
+
+impl GraphicObject for Level {
+    fn draw(&self, context: &mut Context) {
+        let top_row = cell_rectangle(context, 0.0, HOME_ROW as f32, GRID_DIM.x);
+        let grid = Rectangle::new(top_row.position, Vector2::new(top_row.get_size().x, context.rel.height(GRID_SIZE.y))).unwrap();
+
+        context.sdl_renderer.set_draw_color(HEDGE);
+        context.sdl_renderer.fill_rect(top_row.to_sdl_rectangle());
+
+        for (index, home) in self.homes.iter().enumerate() {
+            context.sdl_renderer.set_draw_color(if home.filled { FILLED } else { BAY });
+            context.sdl_renderer.fill_rect(cell_rectangle(context, home.column, HOME_ROW as f32, 1.0).to_sdl_rectangle());
+
+            if let Some((fly_home, _)) = self.fly {
+                if fly_home == index {
+                    context.sdl_renderer.set_draw_color(FLY);
+                    context.sdl_renderer.fill_rect(cell_rectangle(context, home.column + 0.35, HOME_ROW as f32, 0.3).to_sdl_rectangle());
+                }
+            }
+        }
+
+        // Objects wrapping around off the grid shouldn't show
+        context.sdl_renderer.set_clip_rect(Some(grid.to_sdl_rectangle()));
+
+        for (row, lane) in self.lanes.iter().enumerate() {
+            draw_lane(context, lane, row as f32);
+        }
+
+        context.sdl_renderer.set_clip_rect(None);
+    }
+}
+
+/// Draws the cars or logs of a lane sitting in a (possibly fractional) row
+pub fn draw_lane(context: &mut Context, lane: &Lane, row: f32) {
+    let color = match lane.kind {
+        LaneKind::Road => CAR,
+        LaneKind::River => LOG,
+        LaneKind::Safe | LaneKind::Homes => return,
+    };
+
+    context.sdl_renderer.set_draw_color(color);
+    for (left, width) in lane.get_spans(context.interpolation) {
+        let region = cell_rectangle(context, left, row, width);
+
+        // Leave a little space between neighbours and lanes
+        let inset = Vector2::new(1.0, 1.0) * region.get_size().y * 0.08;
+        let region = Rectangle::new(region.position + inset, region.get_size() - inset * 2.0).unwrap();
+
+        context.sdl_renderer.fill_rect(region.to_sdl_rectangle());
+    }
+}
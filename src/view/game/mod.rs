@@ -17,14 +17,20 @@
 // Copyright 2016 Chris Foster
 //
 
+use std::any::Any;
+
 use context::Context;
 use graphics::GraphicObject;
-use input::Input;
+use input::{Input, InputState};
 use math::Vector2;
-use view;
-use view::{View, ViewAction, ViewData};
+use random;
+use save::{self, SaveData};
+use view::{GameOverView, LevelCompleteView, MainMenuView, NextLevel, PauseView, QuitToMenu, Restart, Transition, TransitionKind, View, ViewAction, ViewData};
 
 use self::actors::Frog;
+use self::hud::Hud;
+use self::level::{Landing, Level, START_ROW};
+use self::score::TIME_BONUS_POINTS;
 use self::terrain::{Ground, River, Road};
 
 const GRID_SIZE: Vector2 = Vector2 {
@@ -42,49 +48,410 @@ const GRID_CELL_SIZE: Vector2 = Vector2 {
     y: GRID_SIZE.y / GRID_DIM.y,
 };
 
+/// Returns the screen position of the center of a (possibly fractional) grid cell
+fn cell_position(context: &Context, cell: Vector2) -> Vector2 {
+    Vector2::new(
+        context.rel.center_width(context.rel.height(GRID_SIZE.x)) + context.rel.height((cell.x + 0.5) * GRID_CELL_SIZE.x),
+        context.rel.height((cell.y + 0.5) * GRID_CELL_SIZE.y),
+    )
+}
+
 pub struct GameView {
     ground: Ground,
     road: Road,
     river: River,
-    
+
+    difficulty: Difficulty,
+    level: Level,
     frog: Frog,
+    hud: Hud,
+
+    lives: u32,
+    /// What the levels before this one scored, and this one so far
+    score: Score,
+    level_score: Score,
+    time_left_ns: i64,
+    /// The furthest row up the frog has reached since it last started out
+    best_row: usize,
+    /// The row the frog last died in and how many times in a row it has,
+    /// for the adaptive difficulty
+    adaptive_deaths: Option<(usize, u32)>,
+    /// The high score when the run started, so results screens can tell if it was beaten
+    high_score: u32,
+    /// Set when the level complete screen says to go on
+    next_level_pending: bool,
+    /// Set when a menu over the game says to save and leave
+    quit_pending: bool,
+    /// Set when a menu over the game says to start over
+    restart_pending: bool,
+    /// The save slot this run was resumed from or last saved to
+    slot: Option<usize>,
 }
 
 impl GameView {
     pub fn new(context: &mut Context) -> GameView {
-        GameView {
+        let difficulty = Difficulty::load(&context.config);
+        let level = Level::new(1, &difficulty, context.random.stream(random::GAMEPLAY));
+
+        let mut game_view = GameView {
             ground: Ground::new(),
             road: Road::new(context),
             river: River::new(context),
-            
-            frog: Frog::new(context),
+
+            time_left_ns: level.time_limit_ns,
+            difficulty: difficulty,
+            level: level,
+            frog: Frog::new(context, start_cell()),
+            hud: Hud::new(context),
+
+            lives: difficulty.lives,
+            score: Score::default(),
+            level_score: Score::default(),
+            best_row: START_ROW,
+            adaptive_deaths: None,
+            high_score: context.config.get_or("high_score", 0),
+            next_level_pending: false,
+            quit_pending: false,
+            restart_pending: false,
+            slot: None,
+        };
+
+        game_view.refresh_hud(context);
+        game_view
+    }
+
+    /// Resumes a saved run.  The RNG streams pick up where they were.
+    pub fn load(context: &mut Context, slot: usize) -> Result<GameView, String> {
+        let data = try!(save::read(slot).map_err(|error| error.to_string()));
+
+        let mut game_view = GameView::new(context);
+        game_view.difficulty = try!(Difficulty::read(&data));
+        game_view.level = try!(Level::load(&data));
+        try!(game_view.frog.load(context, &data));
+
+        game_view.lives = try!(data.get("lives"));
+        game_view.score = try!(Score::load(&data, "score"));
+        game_view.level_score = try!(Score::load(&data, "level_score"));
+        game_view.time_left_ns = try!(data.get("time_left_ns"));
+        game_view.best_row = try!(data.get("best_row"));
+        game_view.adaptive_deaths = match try!(data.get::<String>("adaptive.deaths")).as_str() {
+            "none" => None,
+            deaths => {
+                let numbers = deaths.split_whitespace().map(|n| n.parse().ok()).collect::<Vec<Option<usize>>>();
+                match (numbers.get(0), numbers.get(1)) {
+                    (Some(&Some(row)), Some(&Some(count))) => Some((row, count as u32)),
+                    _ => return Err(format!("bad adaptive.deaths: {}", deaths)),
+                }
+            },
+        };
+        game_view.slot = Some(slot);
+
+        if game_view.lives == 0 {
+            return Err("no lives left".to_string());
         }
+
+        // Last, so making the view above doesn't use up the streams
+        context.random.reseed(try!(data.get("seed")));
+        for &name in [random::GAMEPLAY, random::COSMETIC].iter() {
+            if let Ok(state) = data.get(&format!("rng.{}", name)) {
+                context.random.set_state(name, state);
+            }
+        }
+
+        game_view.refresh_hud(context);
+        info!("Resumed level {} from save slot {}", game_view.level.number, slot + 1);
+        Ok(game_view)
+    }
+
+    /// Saves the run to its slot, or a new one if it hasn't been saved yet
+    fn save(&mut self, context: &Context) {
+        // Resuming a finished level would leave the frog with nowhere to go,
+        // and a finished run has nothing to resume
+        if self.level.is_complete() || self.lives == 0 || context.replaying {
+            return;
+        }
+
+        let mut data = SaveData::new();
+
+        data.set("seed", context.random.get_seed());
+        for (name, state) in context.random.get_states() {
+            data.set(&format!("rng.{}", name), state);
+        }
+
+        self.difficulty.write(&mut data);
+        self.level.save(&mut data);
+        self.frog.save(&mut data);
+
+        data.set("lives", self.lives);
+        self.score.save(&mut data, "score");
+        self.level_score.save(&mut data, "level_score");
+        data.set("time_left_ns", self.time_left_ns);
+        data.set("best_row", self.best_row);
+        data.set("adaptive.deaths", match self.adaptive_deaths {
+            Some((row, deaths)) => format!("{} {}", row, deaths),
+            None => "none".to_string(),
+        });
+
+        let slot = self.slot.unwrap_or_else(save::pick_slot);
+        match save::write(slot, &data) {
+            Ok(_) => self.slot = Some(slot),
+            Err(error) => error!("Could not save the game to slot {}: {}", slot + 1, error),
+        }
+    }
+
+    /// The whole run's score so far
+    fn get_total(&self) -> u32 {
+        self.score.get_total() + self.level_score.get_total()
+    }
+
+    fn refresh_hud(&mut self, context: &Context) {
+        let time_left = self.time_left_ns as f32 / self.level.time_limit_ns as f32;
+        let total = self.get_total();
+        self.hud.set(context, "Level", self.level.number, total, self.lives, Some(time_left));
+    }
+
+    /// Sends the frog back to the start with a full timer
+    fn restart_frog(&mut self, context: &Context) {
+        self.frog.respawn(context, start_cell());
+        self.time_left_ns = self.level.time_limit_ns;
+        self.best_row = START_ROW;
+    }
+
+    fn die(&mut self, context: &mut Context) -> Option<ViewAction> {
+        let row = self.frog.get_cell().y as usize;
+
+        self.lives -= 1;
+        debug!("Frog died in row {}, {} lives left", row, self.lives);
+
+        if self.difficulty.adaptive {
+            let deaths = match self.adaptive_deaths {
+                Some((last_row, deaths)) if last_row == row => deaths + 1,
+                _ => 1,
+            };
+            self.adaptive_deaths = Some((row, deaths));
+
+            // Ease off from the second death in a row there
+            if deaths >= 2 && self.level.ease_lane(row, deaths - 2) {
+                debug!("Slowed row {} after {} deaths", row, deaths);
+            }
+        }
+
+        if self.lives == 0 {
+            // Nothing left to resume
+            if let Some(slot) = self.slot.take() {
+                if !context.replaying {
+                    save::delete(slot);
+                }
+            }
+
+            let mut score = self.score;
+            score.add(&self.level_score);
+
+            let title = format!("Game Over on Level {}", self.level.number);
+            let game_over = GameOverView::new(context, &title, score.get_breakdown(), "high_score", self.high_score);
+            return Some(ViewAction::Push(Box::new(game_over)));
+        }
+
+        self.restart_frog(context);
+        None
+    }
+
+    fn next_level(&mut self, context: &mut Context) {
+        self.score.add(&self.level_score);
+        self.level_score = Score::default();
+
+        self.level = Level::new(self.level.number + 1, &self.difficulty, context.random.stream(random::GAMEPLAY));
+        self.adaptive_deaths = None;
+        self.restart_frog(context);
+        self.refresh_hud(context);
     }
 }
 
 impl View for GameView {
+    fn get_name(&self) -> &'static str {
+        "GameView"
+    }
+
     fn get_view_data(&self) -> ViewData {
-        let mut graphic_objects = vec![
+        let graphic_objects = vec![
             &self.ground as &GraphicObject,
             &self.road,
             &self.river,
-            
+
+            &self.level,
             &self.frog,
+            &self.hud,
         ];
-        
+
         ViewData {
             graphic_objects: graphic_objects,
         }
     }
     
-    fn update(&mut self, context: &mut Context, input: Vec<Input>, elapsed_ns: i64) -> (Option<ViewAction>, Vec<Input>) {
-        if input.iter().find(|input| **input == Input::Pause).is_some() {
-            return (Some(ViewAction::SetView(Box::new(view::MainMenuView::new(context)))), Vec::new());
+    fn update(&mut self, context: &mut Context, input: Vec<Input>, _: &InputState, elapsed_ns: i64) -> (Option<ViewAction>, Vec<Input>) {
+        if self.restart_pending {
+            let game_view = Box::new(GameView::new(context));
+            return (Some(ViewAction::TransitionTo(game_view, Transition::new(TransitionKind::Fade, 600))), Vec::new());
+        }
+
+        if self.next_level_pending {
+            self.next_level_pending = false;
+            self.next_level(context);
+        }
+
+        if self.quit_pending {
+            self.save(context);
+
+            let main_menu = Box::new(MainMenuView::new(context));
+            return (Some(ViewAction::TransitionTo(main_menu, Transition::new(TransitionKind::Slide, 400))), Vec::new());
+        }
+
+        // The game over screen is up
+        if self.lives == 0 {
+            return (None, Vec::new());
+        }
+
+        // Losing focus pauses too, so the frog isn't run over while nobody's looking
+        if input.iter().find(|input| **input == Input::Pause || **input == Input::FocusLost).is_some() {
+            return (Some(ViewAction::Push(Box::new(PauseView::new(context)))), Vec::new());
+        }
+
+        for i in input.iter() {
+            let offset = match input_hop(context, &self.frog, i) {
+                Some(offset) => offset,
+                None => continue,
+            };
+
+            // Off the river, the frog lines back up with the columns
+            let cell = self.frog.get_cell();
+            let offset = if self.level.get_drift((cell.y + offset.y) as usize) == 0.0 {
+                Vector2::new(offset.x + cell.x.round() - cell.x, offset.y)
+            } else {
+                offset
+            };
+
+            if self.frog.hop(offset) {
+                let row = self.frog.get_cell().y as usize;
+                if row < self.best_row {
+                    self.best_row = row;
+                    self.level_score.hops += 1;
+                }
+                break;
+            }
+        }
+
+        self.level.update(context.random.stream(random::GAMEPLAY), elapsed_ns);
+
+        let drift = self.level.get_drift(self.frog.get_cell().y as usize);
+        self.frog.carry(drift * elapsed_ns as f32 / 1_000_000_000.0);
+
+        let landed = self.frog.update(context, elapsed_ns);
+        self.time_left_ns -= elapsed_ns;
+
+        let landing = if self.time_left_ns <= 0 {
+            Landing::Died
+        } else if landed {
+            self.level.land(self.frog.get_cell())
+        } else if !self.frog.is_hopping() && self.level.is_deadly(self.frog.get_cell()) {
+            Landing::Died
+        } else {
+            Landing::Safe
+        };
+
+        let view_action = match landing {
+            Landing::Safe => None,
+            Landing::Died => self.die(context),
+            Landing::Home(fly) => {
+                self.level_score.homes += 1;
+                self.level_score.time_bonus += (self.time_left_ns / 1_000_000_000) as u32 * TIME_BONUS_POINTS;
+                if fly {
+                    self.level_score.flies += 1;
+                }
+
+                if self.level.is_complete() {
+                    let total = self.get_total();
+                    Some(ViewAction::Push(Box::new(LevelCompleteView::new(context, self.level_score, total, self.level.number, self.high_score))))
+                } else {
+                    self.restart_frog(context);
+                    None
+                }
+            },
+        };
+
+        self.refresh_hud(context);
+
+        (view_action, Vec::new())
+    }
+
+    fn resize(&mut self, context: &mut Context) {
+        self.road = Road::new(context);
+        self.river = River::new(context);
+        self.frog.resize(context);
+        self.hud.place(context);
+    }
+
+    fn child_returned(&mut self, value: Box<Any>) {
+        if value.is::<NextLevel>() {
+            self.next_level_pending = true;
+        } else if value.is::<Restart>() {
+            self.restart_pending = true;
+        } else if value.is::<QuitToMenu>() {
+            // Leaving from the level complete screen goes on to the next
+            // level first, so that's what gets saved
+            self.next_level_pending = self.level.is_complete();
+            self.quit_pending = true;
+        }
+    }
+
+    fn exit(&mut self, context: &mut Context) {
+        if self.lives > 0 {
+            if self.level.is_complete() {
+                self.next_level(context);
+            }
+            self.save(context);
         }
-        
-        (None, Vec::new())
     }
 }
 
+/// Turns an input into a hop direction.  Swipes already come through as
+/// directions; a tap hops toward where it landed, relative to the frog.
+fn input_hop(context: &Context, frog: &Frog, input: &Input) -> Option<Vector2> {
+    let offset = match *input {
+        Input::Up => return Some(Vector2::new(0.0, -1.0)),
+        Input::Down => return Some(Vector2::new(0.0, 1.0)),
+        Input::Left => return Some(Vector2::new(-1.0, 0.0)),
+        Input::Right => return Some(Vector2::new(1.0, 0.0)),
+        Input::PointerUp(position) => position - frog.get_transform().position,
+        _ => return None,
+    };
+
+    // Tapping the frog itself does nothing
+    if offset.x.abs() < context.rel.height(GRID_CELL_SIZE.x) / 2.0 &&
+       offset.y.abs() < context.rel.height(GRID_CELL_SIZE.y) / 2.0 {
+        return None;
+    }
+
+    if offset.x.abs() > offset.y.abs() {
+        Some(Vector2::new(offset.x.signum(), 0.0))
+    } else {
+        Some(Vector2::new(0.0, offset.y.signum()))
+    }
+}
+
+fn start_cell() -> Vector2 {
+    Vector2::new((GRID_DIM.x / 2.0).floor(), START_ROW as f32)
+}
+
+pub use self::difficulty::{Difficulty, Preset, Range, PRESETS, GAPS_RANGE, HAZARDS_RANGE, LIVES_RANGE, SPEED_RANGE, TIME_LIMIT_RANGE};
+pub use self::endless::EndlessView;
+pub use self::score::Score;
+pub use self::solver::check_levels;
+
 mod actors;
+mod difficulty;
+mod endless;
+mod hud;
+mod level;
+mod score;
+mod solver;
 mod terrain;
@@ -0,0 +1,79 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use save::SaveData;
+
+pub const HOP_POINTS: u32 = 10;
+pub const HOME_POINTS: u32 = 50;
+pub const FLY_POINTS: u32 = 200;
+/// For each whole second left on the timer when a home is reached
+pub const TIME_BONUS_POINTS: u32 = 10;
+
+/// What a level or a run scored.  Hops only count when they reach a row the
+/// frog hasn't been to since it last started out.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Score {
+    pub hops: u32,
+    pub homes: u32,
+    /// Already in points
+    pub time_bonus: u32,
+    pub flies: u32,
+}
+
+impl Score {
+    pub fn get_total(&self) -> u32 {
+        self.hops * HOP_POINTS +
+        self.homes * HOME_POINTS +
+        self.time_bonus +
+        self.flies * FLY_POINTS
+    }
+
+    pub fn add(&mut self, other: &Score) {
+        self.hops += other.hops;
+        self.homes += other.homes;
+        self.time_bonus += other.time_bonus;
+        self.flies += other.flies;
+    }
+
+    /// A label and the points for each part of the score, for results screens
+    pub fn get_breakdown(&self) -> Vec<(String, u32)> {
+        vec![
+            (format!("Hops x{}", self.hops), self.hops * HOP_POINTS),
+            (format!("Homes x{}", self.homes), self.homes * HOME_POINTS),
+            ("Time bonus".to_string(), self.time_bonus),
+            (format!("Flies eaten x{}", self.flies), self.flies * FLY_POINTS),
+        ]
+    }
+
+    pub fn save(&self, data: &mut SaveData, prefix: &str) {
+        data.set(&format!("{}.hops", prefix), self.hops);
+        data.set(&format!("{}.homes", prefix), self.homes);
+        data.set(&format!("{}.time_bonus", prefix), self.time_bonus);
+        data.set(&format!("{}.flies", prefix), self.flies);
+    }
+
+    pub fn load(data: &SaveData, prefix: &str) -> Result<Score, String> {
+        Ok(Score {
+            hops: try!(data.get(&format!("{}.hops", prefix))),
+            homes: try!(data.get(&format!("{}.homes", prefix))),
+            time_bonus: try!(data.get(&format!("{}.time_bonus", prefix))),
+            flies: try!(data.get(&format!("{}.flies", prefix))),
+        })
+    }
+}
@@ -0,0 +1,493 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+//! Checks that a level can be beaten.  The lanes are stepped forward the
+//! way the game steps them, and a breadth-first search over (cell, time)
+//! finds the earliest the frog can reach each home from the start row,
+//! hopping and waiting by the same rules as in the game.  Each home is
+//! checked from a fresh start, as after the frog reaches one in the game.
+//! The same search checks the blocks of an endless track, from one safe row
+//! to the next.
+//!
+//! check() needs no window, so it can be used from tests as well as by the
+//! check-levels subcommand.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use config::Config;
+use math::Vector2;
+use random::{self, Random};
+use save;
+
+use view::game::{start_cell, Difficulty, GRID_DIM};
+use view::game::actors::HOP_NS;
+use view::game::endless::Track;
+use view::game::level::{Lane, LaneKind, Level};
+
+/// The game's logic step
+const STEP_NS: i64 = 1_000_000_000 / 60;
+
+/// How many steps a hop takes before it lands
+const HOP_STEPS: i64 = (HOP_NS + STEP_NS - 1) / STEP_NS;
+
+/// Endless mode has no timer, so each block gets this long to be crossed
+const BLOCK_TIME_LIMIT_NS: i64 = 30_000_000_000;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+impl Direction {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Direction::Up => "Up",
+            Direction::Down => "Down",
+            Direction::Left => "Left",
+            Direction::Right => "Right",
+        }
+    }
+
+    fn offset(&self) -> Vector2 {
+        match *self {
+            Direction::Up => Vector2::new(0.0, -1.0),
+            Direction::Down => Vector2::new(0.0, 1.0),
+            Direction::Left => Vector2::new(-1.0, 0.0),
+            Direction::Right => Vector2::new(1.0, 0.0),
+        }
+    }
+}
+
+/// One hop of a route, and how long after the start it's pressed
+#[derive(Copy, Clone, Debug)]
+pub struct Hop {
+    pub direction: Direction,
+    pub start_ns: i64,
+}
+
+/// The quickest way found to a home.  Waiting fills the time between hops.
+#[derive(Clone, Debug)]
+pub struct Route {
+    pub hops: Vec<Hop>,
+    /// When the frog lands in the home
+    pub time_ns: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct HomeReport {
+    pub column: f32,
+    /// None if the home can't be reached in time
+    pub route: Option<Route>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub homes: Vec<HomeReport>,
+    pub time_limit_ns: i64,
+}
+
+impl Report {
+    pub fn is_solvable(&self) -> bool {
+        self.homes.iter().all(|home| home.route.is_some())
+    }
+}
+
+/// Somewhere the frog stood at the end of a step
+struct Node {
+    row: usize,
+    x: f32,
+    /// The node this one was reached from, and the hop that did it if it
+    /// wasn't a wait
+    parent: Option<usize>,
+    hop: Option<Hop>,
+}
+
+/// A hop on its way, to land in the given cell
+struct Landing {
+    parent: usize,
+    hop: Hop,
+    row: usize,
+    x: f32,
+}
+
+/// Where the frog is headed, in the top row of the lanes searched
+enum Goal<'a> {
+    /// Each of these columns, found separately.  Landing anywhere else in
+    /// the row is as deadly as a full home.
+    Columns(&'a [f32]),
+    /// Anywhere in the row
+    Row,
+}
+
+/// Positions within a twentieth of a cell are the same for the search, or
+/// the river rows fill up with frogs a hair apart
+fn position_key(row: usize, x: f32) -> (usize, i32) {
+    (row, (x * 20.0).round() as i32)
+}
+
+/// Searches the level from its start.  The level isn't changed.
+pub fn check(level: &Level) -> Report {
+    let columns = level.homes.iter().map(|home| home.column).collect::<Vec<f32>>();
+    let routes = search(level.lanes.clone(), start_cell(), level.time_limit_ns, Goal::Columns(&columns));
+
+    Report {
+        homes: level.homes.iter().zip(routes.into_iter()).map(|(home, route)| HomeReport {
+            column: home.column,
+            route: route,
+        }).collect(),
+        time_limit_ns: level.time_limit_ns,
+    }
+}
+
+/// Searches an endless track from the safe row given to the next safe row
+/// above it, starting in the start column with the lanes as they are now.
+/// Endless mode has no timer, so time_limit_ns only bounds the search.
+/// Returns None if the row isn't safe or the block above isn't made yet.
+pub fn check_block(track: &Track, row: i32, time_limit_ns: i64) -> Option<Route> {
+    if !track.is_safe(row) {
+        return None;
+    }
+
+    let mut lanes = vec![track.get_lane(row).unwrap().clone()];
+    loop {
+        let lane = match track.get_lane(row - lanes.len() as i32) {
+            Some(lane) => lane.clone(),
+            None => return None,
+        };
+
+        let safe = lane.kind == LaneKind::Safe;
+        lanes.insert(0, lane);
+        if safe {
+            break;
+        }
+    }
+
+    let start = Vector2::new(start_cell().x, (lanes.len() - 1) as f32);
+    search(lanes, start, time_limit_ns, Goal::Row).pop().unwrap()
+}
+
+/// The search itself, over lanes given top first.  Returns a route for each
+/// goal column, or just one for Goal::Row.
+fn search(mut lanes: Vec<Lane>, start: Vector2, time_limit_ns: i64, goal: Goal) -> Vec<Option<Route>> {
+    let last_step = (time_limit_ns - 1) / STEP_NS;
+    let step_s = STEP_NS as f32 / 1_000_000_000.0;
+
+    let mut nodes = vec![Node {
+        row: start.y as usize,
+        x: start.x,
+        parent: None,
+        hop: None,
+    }];
+
+    // Safe rows never move or kill, so the first time the frog gets to a
+    // cell in one is the only time that matters: it can wait there for as
+    // long as it likes.  Everywhere else, waiting is searched step by step.
+    let mut resting = vec![0];
+    let mut rested = HashSet::new();
+    rested.insert(position_key(nodes[0].row, nodes[0].x));
+    let mut standing: Vec<usize> = Vec::new();
+
+    let mut landings: Vec<Vec<Landing>> = (0..last_step + HOP_STEPS + 1).map(|_| Vec::new()).collect();
+    let mut routes: Vec<Option<Route>> = match goal {
+        Goal::Columns(columns) => columns.iter().map(|_| None).collect(),
+        Goal::Row => vec![None],
+    };
+
+    for step in 1..last_step + 1 {
+        // Hops pressed this step, from wherever the frog could be standing
+        for &index in resting.iter().chain(standing.iter()) {
+            for &direction in DIRECTIONS.iter() {
+                let node = &nodes[index];
+                let target_row = node.row as f32 + direction.offset().y;
+
+                if target_row < 0.0 || target_row >= lanes.len() as f32 {
+                    continue;
+                }
+
+                // Off the river, the frog lines back up with the columns
+                let drift = get_drift(&lanes, target_row as usize);
+                let mut target_x = node.x + direction.offset().x;
+                if drift == 0.0 {
+                    target_x = target_x.round();
+                }
+
+                if target_x < 0.0 || target_x >= GRID_DIM.x {
+                    continue;
+                }
+
+                landings[(step + HOP_STEPS - 1) as usize].push(Landing {
+                    parent: index,
+                    hop: Hop {
+                        direction: direction,
+                        start_ns: (step - 1) * STEP_NS,
+                    },
+                    row: target_row as usize,
+                    x: target_x + drift * step_s * HOP_STEPS as f32,
+                });
+            }
+        }
+
+        for lane in lanes.iter_mut() {
+            lane.update(STEP_NS);
+        }
+
+        let mut next_standing = Vec::new();
+        let mut seen = HashSet::new();
+
+        for &index in standing.iter() {
+            let (row, x) = (nodes[index].row, nodes[index].x + get_drift(&lanes, nodes[index].row) * step_s);
+
+            if !is_deadly(&lanes, row, x) && seen.insert(position_key(row, x)) {
+                nodes.push(Node {
+                    row: row,
+                    x: x,
+                    parent: Some(index),
+                    hop: None,
+                });
+                next_standing.push(nodes.len() - 1);
+            }
+        }
+
+        for landing in landings[step as usize].drain(..) {
+            if is_deadly(&lanes, landing.row, landing.x) {
+                continue;
+            }
+
+            if landing.row == 0 {
+                let reached = match goal {
+                    Goal::Columns(columns) => columns.iter().position(|column| (column - landing.x).abs() < 0.5),
+                    Goal::Row => Some(0),
+                };
+
+                if let Some(reached) = reached {
+                    if routes[reached].is_none() {
+                        let mut hops = vec![landing.hop];
+                        let mut parent = Some(landing.parent);
+                        while let Some(index) = parent {
+                            hops.extend(nodes[index].hop);
+                            parent = nodes[index].parent;
+                        }
+                        hops.reverse();
+
+                        routes[reached] = Some(Route {
+                            hops: hops,
+                            time_ns: step * STEP_NS,
+                        });
+                    }
+                }
+                continue;
+            }
+
+            let key = position_key(landing.row, landing.x);
+            let safe = lanes[landing.row].kind == LaneKind::Safe;
+
+            if (safe && !rested.insert(key)) || (!safe && !seen.insert(key)) {
+                continue;
+            }
+
+            nodes.push(Node {
+                row: landing.row,
+                x: landing.x,
+                parent: Some(landing.parent),
+                hop: Some(landing.hop),
+            });
+
+            if safe {
+                resting.push(nodes.len() - 1);
+            } else {
+                next_standing.push(nodes.len() - 1);
+            }
+        }
+
+        standing = next_standing;
+
+        if routes.iter().all(|route| route.is_some()) {
+            break;
+        }
+    }
+
+    routes
+}
+
+/// Like Level::get_drift, for the lanes being searched
+fn get_drift(lanes: &[Lane], row: usize) -> f32 {
+    match lanes[row].kind {
+        LaneKind::River => lanes[row].speed,
+        _ => 0.0,
+    }
+}
+
+/// Like Level::is_deadly, for the lanes being searched
+fn is_deadly(lanes: &[Lane], row: usize, x: f32) -> bool {
+    x < -0.5 || x > GRID_DIM.x - 0.5 || lanes[row].is_deadly(x)
+}
+
+/// The check-levels subcommand.  Checks the levels in the given files, in
+/// the save format, or else the first few generated levels (the levels key,
+/// 10 by default) for the configured seed and difficulty.  With the endless
+/// key set, checks the endless track instead.  Prints a report and returns
+/// whether every level can be beaten.
+pub fn check_levels(config: &Config, paths: &[String]) -> bool {
+    if config.get_or("endless", false) {
+        return check_track(config);
+    }
+
+    let mut levels = Vec::new();
+
+    if paths.is_empty() {
+        let seed = config.get_or("seed", Random::random_seed());
+        let difficulty = Difficulty::load(config);
+        let mut random = Random::new(seed);
+
+        println!("Generating levels from seed {} on {}", seed, difficulty.preset.name());
+        for number in 1..config.get_or("levels", 10u32) + 1 {
+            levels.push((format!("Level {}", number), Level::new(number, &difficulty, random.stream(random::GAMEPLAY))));
+        }
+    } else {
+        for path in paths.iter() {
+            let level = save::read_path(Path::new(path))
+                .map_err(|error| error.to_string())
+                .and_then(|data| Level::load(&data));
+
+            match level {
+                Ok(level) => levels.push((path.clone(), level)),
+                Err(error) => {
+                    println!("{}: could not load: {}", path, error);
+                    return false;
+                },
+            }
+        }
+    }
+
+    let mut all_solvable = true;
+    for &(ref name, ref level) in levels.iter() {
+        let report = check(level);
+
+        println!("{}: {}", name, if report.is_solvable() { "solvable" } else { "NOT SOLVABLE" });
+        for home in report.homes.iter() {
+            match home.route {
+                Some(ref route) => {
+                    let hops = route.hops.iter()
+                        .map(|hop| format!("{} {:.2}s", hop.direction.name(), seconds(hop.start_ns)))
+                        .collect::<Vec<String>>();
+                    println!("  Home at column {} in {:.2}s: {}", home.column, seconds(route.time_ns), hops.join(", "));
+                },
+                None => println!("  Home at column {} can't be reached within {:.0}s", home.column, seconds(report.time_limit_ns)),
+            }
+        }
+
+        all_solvable = all_solvable && report.is_solvable();
+    }
+
+    all_solvable
+}
+
+/// Checks each block in the first rows of the endless track (the rows key,
+/// 300 by default) for the configured seed and difficulty
+fn check_track(config: &Config) -> bool {
+    let seed = config.get_or("seed", Random::random_seed());
+    let difficulty = Difficulty::load(config);
+    let rows = config.get_or("rows", 300);
+
+    println!("Checking {} rows of endless track from seed {} on {}", rows, seed, difficulty.preset.name());
+
+    let mut track = Track::new(seed, difficulty, 0.0);
+    let mut row = 0;
+    let mut all_passable = true;
+
+    while -row < rows {
+        // Makes the rest of the block, and a screen more
+        track.scroll_to(row as f32 - GRID_DIM.y);
+
+        let next = (1..GRID_DIM.y as i32).map(|up| row - up).find(|&row| track.is_safe(row)).unwrap();
+        match check_block(&track, row, BLOCK_TIME_LIMIT_NS) {
+            Some(route) => println!("Rows {} to {}: {} hops in {:.2}s", -row, -next, route.hops.len(), seconds(route.time_ns)),
+            None => {
+                println!("Rows {} to {}: NOT PASSABLE", -row, -next);
+                all_passable = false;
+            },
+        }
+
+        row = next;
+    }
+
+    all_passable
+}
+
+fn seconds(ns: i64) -> f32 {
+    ns as f32 / 1_000_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use random::{self, Random};
+    use view::game::{start_cell, Difficulty, PRESETS};
+    use view::game::level::{Lane, LaneKind, Level};
+    use super::{check, HOP_STEPS, STEP_NS};
+
+    const SEED: u64 = 0x5eed;
+    const LEVELS: u32 = 5;
+
+    #[test]
+    fn generated_levels_are_solvable() {
+        for &preset in PRESETS.iter() {
+            let difficulty = Difficulty::preset(preset);
+            let mut random = Random::new(SEED);
+
+            for number in 1..LEVELS + 1 {
+                let level = Level::new(number, &difficulty, random.stream(random::GAMEPLAY));
+                assert!(check(&level).is_solvable(), "level {} on {} can't be beaten", number, preset.name());
+            }
+        }
+    }
+
+    #[test]
+    fn empty_river_lane_blocks_every_home() {
+        let mut level = Level::new(1, &Difficulty::preset(PRESETS[0]), Random::new(SEED).stream(random::GAMEPLAY));
+        level.lanes[3] = Lane::new(LaneKind::River, 1.0, Vec::new());
+        // Without a way through, the search goes on to the time limit
+        level.time_limit_ns = 10_000_000_000;
+
+        let report = check(&level);
+        assert!(report.homes.iter().all(|home| home.route.is_none()));
+    }
+
+    #[test]
+    fn open_ground_takes_one_hop_per_cell() {
+        let mut level = Level::new(1, &Difficulty::preset(PRESETS[0]), Random::new(SEED).stream(random::GAMEPLAY));
+        for lane in level.lanes.iter_mut().filter(|lane| lane.kind != LaneKind::Homes) {
+            *lane = Lane::safe();
+        }
+
+        let start = start_cell();
+        for home in check(&level).homes.iter() {
+            let hops = start.y + (home.column - start.x).abs();
+            let route = home.route.as_ref().unwrap();
+
+            assert_eq!(route.hops.len(), hops as usize);
+            assert_eq!(route.time_ns, hops as i64 * HOP_STEPS * STEP_NS);
+        }
+    }
+}
@@ -64,7 +64,7 @@ impl Road {
     }
 }
 
-const ROAD: Color = Color::RGB(80, 80, 80);
+pub const ROAD: Color = Color::RGB(80, 80, 80);
 
 impl GraphicObject for Road {
     fn draw(&self, context: &mut Context) {
@@ -91,7 +91,7 @@ impl River {
     }
 }
 
-const RIVER: Color = Color::RGB(0, 0, 128);
+pub const RIVER: Color = Color::RGB(0, 0, 128);
 
 impl GraphicObject for River {
     fn draw(&self, context: &mut Context) {
@@ -17,110 +17,199 @@
 // Copyright 2016 Chris Foster
 //
 
+use std::any::Any;
+
 use sdl2::pixels::Color;
 use sdl2::rect::Rect as SdlRectangle;
 
 use context::Context;
-use graphics::{GraphicObject, Sprite};
-use input;
-use input::{Button, Input};
-use math::{Vector2, Rectangle};
-use view::{GameView, View, ViewAction, ViewData};
+use graphics::{Align, GraphicObject, Sprite, TextStyle};
+use input::{Input, InputState};
+use math::Rectangle;
+use save;
+use ui::{Anchor, Button, Direction, Focus, Layout, Length, Slot, Widget, WidgetEvent, SCREEN};
+use view::{EndlessView, GameView, OptionsView, Transition, TransitionKind, View, ViewAction, ViewData};
 
 pub struct MainMenuView {
     backdrop: Backdrop,
 
     title: Title,
 
-    play_button: MenuButton,
-    options_button: MenuButton,
-    scores_button: MenuButton,
-    quit_button: MenuButton,
+    /// Only there when a save exists.  During a replay it's there if it was
+    /// when recording, with no slot.
+    continue_button: Option<Button>,
+    continue_slot: Option<usize>,
+    play_button: Button,
+    endless_button: Button,
+    options_button: Button,
+    scores_button: Button,
+    quit_button: Button,
 
     version: Sprite,
     author: Sprite,
 
-    highlighted: Option<usize>,
+    crash_notice: Option<Sprite>,
+
+    layout: Layout,
+    title_slot: Slot,
+    button_slots: Vec<Slot>,
+    version_slot: Slot,
+    author_slot: Slot,
+    crash_notice_slot: Slot,
+
+    focus: Focus,
     in_child_view: bool,
 }
 
 impl MainMenuView {
     pub fn new(context: &mut Context) -> MainMenuView {
         let info_font = context.font_renderer.load_font("assets/fonts/jim_teacher.ttf", context.rel.height(0.035) as u16);
+        let menu_font = context.font_renderer.load_font("assets/fonts/fff_aquarius_bold.ttf", context.rel.height(0.06) as u16);
 
-        let button_size = Vector2::new(
-            context.rel.width(0.5),
-            context.rel.height(0.1),
-        );
+        let version = match context.font_renderer.render_sprite(context, &info_font, "v0.1", Color::RGB(255, 255, 255)) {
+            Some(sprite) => sprite,
+            None => panic!("Could not render version text!"),
+        };
 
-        let button_start = Vector2::new(
-            context.rel.center_width(button_size.x),
-            context.rel.height(0.47),
-        );
-        let button_pad = context.rel.height(0.022);
+        let author = match context.font_renderer.render_sprite(context, &info_font, "©2016 Chris Foster", Color::RGB(255, 255, 255)) {
+            Some(sprite) => sprite,
+            None => panic!("Could not render author text!"),
+        };
+
+        // Only shown the first time the menu comes up after a crash
+        let crash_notice = context.crash_report.take().and_then(|path| {
+            let text = format!("The game crashed last time. A report was saved to {}", path.display());
+            let mut style = TextStyle::new(Align::Center);
+            style.max_width = Some(context.rel.width(0.9));
+            style.max_lines = Some(3);
+
+            context.font_renderer.render_text(context, &info_font, &text, Color::RGB(255, 255, 255), &style)
+        });
+
+        let mut layout = Layout::new();
+
+        let title_slot = layout.add(SCREEN, Anchor::Top, Length::Percent(100.0), Length::Percent(54.0));
 
-        MainMenuView {
+        // The saves on disk now may not be the ones there when the replay was
+        // recorded, and Continue moves every button after it
+        let (continue_slot, show_continue) = if context.replaying {
+            (None, context.replay_continue)
+        } else {
+            let slot = save::latest_slot();
+            (slot, slot.is_some())
+        };
+
+        // Squeeze the buttons up a bit to fit Continue in
+        let (button_count, button_height, spacing) = if show_continue {
+            (6, 7.0, 1.4)
+        } else {
+            (5, 8.5, 1.6)
+        };
+
+        let menu = layout.add(SCREEN, Anchor::Bottom, Length::Percent(50.0), Length::Percent(53.0));
+        layout.set_stack(menu, Direction::Vertical, Length::ScreenHeight(spacing));
+
+        let button_slots = (0..button_count).map(|_| {
+            layout.add(menu, Anchor::Top, Length::Percent(100.0), Length::ScreenHeight(button_height))
+        }).collect::<Vec<Slot>>();
+        let first = button_count - 5;
+
+        let version_slot = sprite_slot(&mut layout, &version, Anchor::BottomLeft);
+        let author_slot = sprite_slot(&mut layout, &author, Anchor::BottomRight);
+        let crash_notice_slot = match crash_notice {
+            Some(ref crash_notice) => sprite_slot(&mut layout, crash_notice, Anchor::Top),
+            None => SCREEN,
+        };
+
+        layout.compute(context.screen_size);
+
+        let mut main_menu_view = MainMenuView {
             backdrop: Backdrop::new(),
 
             title: Title::new(context),
 
-            play_button: MenuButton::new(context, "Play Game", Rectangle::new(
-                Vector2::new(
-                    button_start.x,
-                    0 as f32 * (button_size.y + button_pad) + button_start.y,
-                ),
-                button_size,
-            ).unwrap()),
-
-            options_button: MenuButton::new(context, "Options", Rectangle::new(
-                Vector2::new(
-                    button_start.x,
-                    1 as f32 * (button_size.y + button_pad) + button_start.y,
-                ),
-                button_size,
-            ).unwrap()),
-
-            scores_button: MenuButton::new(context, "Leaderboards", Rectangle::new(
-                Vector2::new(
-                    button_start.x,
-                    2 as f32 * (button_size.y + button_pad) + button_start.y,
-                ),
-                button_size,
-            ).unwrap()),
-
-            quit_button: MenuButton::new(context, "Exit Game", Rectangle::new(
-                Vector2::new(
-                    button_start.x,
-                    3 as f32 * (button_size.y + button_pad) + button_start.y,
-                ),
-                button_size,
-            ).unwrap()),
-
-            version: match context.font_renderer.render_sprite(context, &info_font, "v0.1", Color::RGB(255, 255, 255)) {
-                Some(mut sprite) => {
-                    sprite.transform.position.x = sprite.get_output_region().0.get_size().x / 2.0 + context.rel.height(0.008);
-                    sprite.transform.position.y = context.screen_size.y - sprite.get_output_region().0.get_size().y / 2.0 + context.rel.height(0.005);
-                    sprite
-                },
-                None => panic!("Could not render version text!"),
+            continue_button: if show_continue {
+                Some(Button::new(context, &menu_font, "Continue", layout.get(button_slots[0])))
+            } else {
+                None
             },
+            continue_slot: continue_slot,
+            play_button: Button::new(context, &menu_font, "Play Game", layout.get(button_slots[first])),
+            endless_button: Button::new(context, &menu_font, "Endless", layout.get(button_slots[first + 1])),
+            options_button: Button::new(context, &menu_font, "Options", layout.get(button_slots[first + 2])),
+            scores_button: Button::new(context, &menu_font, "Leaderboards", layout.get(button_slots[first + 3])),
+            quit_button: Button::new(context, &menu_font, "Exit Game", layout.get(button_slots[first + 4])),
+
+            version: version,
+            author: author,
+
+            crash_notice: crash_notice,
+
+            layout: layout,
+            title_slot: title_slot,
+            button_slots: button_slots,
+            version_slot: version_slot,
+            author_slot: author_slot,
+            crash_notice_slot: crash_notice_slot,
+
+            focus: Focus::new(),
+            in_child_view: false,
+        };
 
-            author: match context.font_renderer.render_sprite(context, &info_font, "©2016 Chris Foster", Color::RGB(255, 255, 255)) {
-                Some(mut sprite) => {
-                    sprite.transform.position.x = context.screen_size.x - sprite.get_output_region().0.get_size().x / 2.0 - context.rel.height(0.008);
-                    sprite.transform.position.y = context.screen_size.y - sprite.get_output_region().0.get_size().y / 2.0 + context.rel.height(0.005);
-                    sprite
-                },
-                None => panic!("Could not render author text!"),
-            },
+        main_menu_view.place(context);
+        main_menu_view
+    }
 
-            highlighted: None,
-            in_child_view: false,
+    /// Top to bottom
+    fn get_buttons_mut(&mut self) -> Vec<&mut Button> {
+        let mut buttons = Vec::new();
+
+        if let Some(ref mut continue_button) = self.continue_button {
+            buttons.push(continue_button);
         }
+
+        buttons.push(&mut self.play_button);
+        buttons.push(&mut self.endless_button);
+        buttons.push(&mut self.options_button);
+        buttons.push(&mut self.scores_button);
+        buttons.push(&mut self.quit_button);
+        buttons
     }
+
+    /// Moves everything to where the layout says it goes
+    fn place(&mut self, context: &Context) {
+        self.layout.compute(context.screen_size);
+
+        self.title.text_sprite.transform.position = self.layout.get(self.title_slot).get_center();
+
+        let regions = self.button_slots.iter().map(|&slot| self.layout.get(slot)).collect::<Vec<Rectangle>>();
+        for (button, region) in self.get_buttons_mut().into_iter().zip(regions.into_iter()) {
+            button.set_region(context, region);
+        }
+
+        self.version.transform.position = self.layout.get(self.version_slot).get_center();
+        self.author.transform.position = self.layout.get(self.author_slot).get_center();
+
+        if let Some(ref mut crash_notice) = self.crash_notice {
+            crash_notice.transform.position = self.layout.get(self.crash_notice_slot).get_center();
+        }
+    }
+}
+
+/// Adds a slot the size of a sprite, a little way in from the screen's edge
+fn sprite_slot(layout: &mut Layout, sprite: &Sprite, anchor: Anchor) -> Slot {
+    let size = sprite.get_output_region().0.get_size();
+
+    let slot = layout.add(SCREEN, anchor, Length::Pixels(size.x), Length::Pixels(size.y));
+    layout.set_margin(slot, Length::ScreenHeight(0.8));
+    slot
 }
 
 impl View for MainMenuView {
+    fn get_name(&self) -> &'static str {
+        "MainMenuView"
+    }
+
     fn get_view_data(&self) -> ViewData {
         let mut graphic_objects = vec![
             &self.backdrop as &GraphicObject,
@@ -131,6 +220,7 @@ impl View for MainMenuView {
                 &self.title as &GraphicObject,
 
                 &self.play_button,
+                &self.endless_button,
                 &self.options_button,
                 &self.scores_button,
                 &self.quit_button,
@@ -138,6 +228,14 @@ impl View for MainMenuView {
                 &self.version,
                 &self.author,
             ].iter());
+
+            if let Some(ref continue_button) = self.continue_button {
+                graphic_objects.push(continue_button);
+            }
+
+            if let Some(ref crash_notice) = self.crash_notice {
+                graphic_objects.push(crash_notice);
+            }
         }
 
         ViewData {
@@ -145,7 +243,7 @@ impl View for MainMenuView {
         }
     }
 
-    fn update(&mut self, context: &mut Context, input: Vec<Input>, _: i64) -> (Option<ViewAction>, Vec<Input>) {
+    fn update(&mut self, context: &mut Context, input: Vec<Input>, state: &InputState, _: i64) -> (Option<ViewAction>, Vec<Input>) {
         if self.in_child_view {
             if input.iter().find(|input| **input == Input::Pause).is_some() {
                 self.in_child_view = false;
@@ -154,55 +252,78 @@ impl View for MainMenuView {
             }
         }
 
-        let mut menu_buttons: Vec<&mut MenuButton> = vec![
-            &mut self.play_button,
-            &mut self.options_button,
-            &mut self.scores_button,
-            &mut self.quit_button,
-        ];
+        let (event, _) = {
+            let mut buttons: Vec<&mut Widget> = Vec::new();
+            if let Some(ref mut continue_button) = self.continue_button {
+                buttons.push(continue_button);
+            }
+            buttons.push(&mut self.play_button);
+            buttons.push(&mut self.endless_button);
+            buttons.push(&mut self.options_button);
+            buttons.push(&mut self.scores_button);
+            buttons.push(&mut self.quit_button);
 
-        let (highlighted, selected) = input::menu(
-            &menu_buttons.iter().map(|x| *x as &Button).collect(), // Get a list of immutable buttons
-            self.highlighted,
-            &input,
-        );
+            self.focus.update(context, &mut buttons, &input, state)
+        };
 
-        self.highlighted = highlighted;
-        for (menu_index, menu_button) in menu_buttons.iter_mut().enumerate() {
-            match self.highlighted {
-                Some(ref index) => {
-                    if *index == menu_index {
-                        menu_button.hover = true;
-                    } else {
-                        menu_button.hover = false;
-                    }
-                },
-                None => { menu_button.hover = false; },
-            }
-        }
+        // Number the buttons as if Continue weren't there, with it as None
+        let event = match (event, self.continue_button.is_some()) {
+            (Some((0, event)), true) => Some((None, event)),
+            (Some((index, event)), true) => Some((Some(index - 1), event)),
+            (Some((index, event)), false) => Some((Some(index), event)),
+            (None, _) => None,
+        };
 
-        let view_action = match selected {
-            Some(ref index) => {
-                if *index == 0 {
-                    Some(ViewAction::SetView(Box::new(GameView::new(context))))
-                } else if *index == 1 {
-                    self.in_child_view = true;
-                    //Some(ViewAction::AddView(OptionsView::new(context)))
-                    None
-                } else if *index == 2 {
-                    self.in_child_view = true;
-                    //Some(ViewAction::AddView(LeaderboardsView::new(context)))
-                    None
-                } else {
-                    Some(ViewAction::ExitGame)
+        let view_action = match event {
+            Some((None, WidgetEvent::Pressed)) => {
+                // The save on disk now may not be the one the replay started from
+                let slot = match self.continue_slot {
+                    Some(slot) => slot,
+                    None => {
+                        warn!("Can't continue a saved game during a replay");
+                        return (None, Vec::new());
+                    },
+                };
+
+                match GameView::load(context, slot) {
+                    Ok(game_view) => Some(ViewAction::TransitionTo(Box::new(game_view), Transition::new(TransitionKind::Fade, 600))),
+                    Err(error) => {
+                        error!("Could not resume save slot {}: {}", slot + 1, error);
+                        self.continue_button = None;
+                        self.continue_slot = None;
+                        None
+                    },
                 }
             },
-            None => None,
+            Some((Some(0), WidgetEvent::Pressed)) => {
+                Some(ViewAction::TransitionTo(Box::new(GameView::new(context)), Transition::new(TransitionKind::Fade, 600)))
+            },
+            Some((Some(1), WidgetEvent::Pressed)) => {
+                Some(ViewAction::TransitionTo(Box::new(EndlessView::new(context)), Transition::new(TransitionKind::Fade, 600)))
+            },
+            Some((Some(2), WidgetEvent::Pressed)) => {
+                self.in_child_view = true;
+                Some(ViewAction::Push(Box::new(OptionsView::new(context))))
+            },
+            Some((Some(3), WidgetEvent::Pressed)) => {
+                self.in_child_view = true;
+                //Some(ViewAction::Push(LeaderboardsView::new(context)))
+                None
+            },
+            Some((Some(4), WidgetEvent::Pressed)) => Some(ViewAction::ExitGame),
+            _ => None,
         };
 
-
         (view_action, Vec::new())
     }
+
+    fn resize(&mut self, context: &mut Context) {
+        self.place(context);
+    }
+
+    fn child_returned(&mut self, _: Box<Any>) {
+        self.in_child_view = false;
+    }
 }
 
 const BACKDROP: Color = Color::RGB(64, 155, 0);
@@ -228,6 +349,7 @@ impl GraphicObject for Backdrop {
     }
 }
 
+/// Positioned by MainMenuView's layout
 struct Title {
     text_sprite: Sprite,
 }
@@ -237,13 +359,7 @@ impl Title {
         let title_font = context.font_renderer.load_font("assets/fonts/jim_teacher.ttf", context.rel.height(0.25) as u16);
 
         let text_sprite = match context.font_renderer.render_sprite(context, &title_font, "The Reaping", Color::RGB(255, 255, 255)) {
-            Some(mut sprite) => {
-                sprite.transform.position = Vector2::new(
-                    context.rel.width(0.5),
-                    context.rel.height(0.27),
-                );
-                sprite
-            },
+            Some(sprite) => sprite,
             None => panic!("Could not render title text!"),
         };
 
@@ -258,57 +374,3 @@ impl GraphicObject for Title {
         self.text_sprite.draw(context);
     }
 }
-
-const MENU_NORMAL: Color = Color::RGB(75, 185, 30);
-const MENU_HIGHLIGHT: Color = Color::RGB(100, 210, 50);
-
-struct MenuButton {
-    text_sprite: Sprite,
-    region: Rectangle,
-
-    hover: bool,
-}
-
-impl MenuButton {
-    pub fn new(context: &mut Context, text: &str, region: Rectangle) -> MenuButton {
-        let menu_font = context.font_renderer.load_font("assets/fonts/fff_aquarius_bold.ttf", context.rel.height(0.06) as u16);
-
-        let text_sprite = match context.font_renderer.render_sprite(context, &menu_font, text, Color::RGB(255, 255, 255)) {
-            Some(mut sprite) => {
-                sprite.transform.position = Vector2::new(
-                    region.position.x + region.get_size().x / 2.0,
-                    region.position.y + region.get_size().y / 2.0,
-                );
-                sprite
-            },
-            None => panic!("Could not render menu text: {}", text),
-        };
-
-        MenuButton {
-            text_sprite: text_sprite,
-            region: region,
-
-            hover: false,
-        }
-    }
-}
-
-impl Button for MenuButton {
-    fn get_region(&self) -> Rectangle {
-        self.region
-    }
-}
-
-impl GraphicObject for MenuButton {
-    fn draw(&self, context: &mut Context) {
-        if !self.hover {
-            context.sdl_renderer.set_draw_color(MENU_NORMAL);
-        } else {
-            context.sdl_renderer.set_draw_color(MENU_HIGHLIGHT);
-        }
-
-        context.sdl_renderer.fill_rect(self.region.to_sdl_rectangle());
-
-        self.text_sprite.draw(context);
-    }
-}
@@ -17,16 +17,55 @@
 // Copyright 2016 Chris Foster
 //
 
+use std::any::Any;
+
 use context::Context;
 use graphics::GraphicObject;
-use input::Input;
+use input::{Input, InputState};
 //use sound::SoundObject;
 
 pub trait View {
+    /// A short name for logs and crash reports
+    fn get_name(&self) -> &'static str;
+
     fn get_view_data(&self) -> ViewData;
 
-    /// Any input returned will be passed to the next view.
-    fn update(&mut self, context: &mut Context, input: Vec<Input>, elapsed_ns: i64) -> (Option<ViewAction>, Vec<Input>);
+    /// Any input returned will be passed to the next view.  state tracks which
+    /// actions are held.  elapsed_ns is game time; views that must keep running
+    /// while the game is paused or slowed should use
+    /// context.clock.real_elapsed_ns() instead.
+    fn update(&mut self, context: &mut Context, input: Vec<Input>, state: &InputState, elapsed_ns: i64) -> (Option<ViewAction>, Vec<Input>);
+
+    /// Called after the screen size changes, so the view can lay itself out again
+    fn resize(&mut self, _: &mut Context) {}
+
+    fn get_flags(&self) -> ViewFlags {
+        ViewFlags::default()
+    }
+
+    /// Called with the value a view pushed by this one returned
+    fn child_returned(&mut self, _: Box<Any>) {}
+
+    /// Called when the game is closing, e.g. to save progress
+    fn exit(&mut self, _: &mut Context) {}
+}
+
+/// How a view affects the views below it
+#[derive(Copy, Clone, Debug)]
+pub struct ViewFlags {
+    /// The views below get no input, whatever this view passes on
+    pub blocks_input_below: bool,
+    /// The views below are drawn under this one
+    pub renders_below: bool,
+}
+
+impl Default for ViewFlags {
+    fn default() -> ViewFlags {
+        ViewFlags {
+            blocks_input_below: false,
+            renders_below: true,
+        }
+    }
 }
 
 pub struct ViewData<'a> {
@@ -35,14 +74,39 @@ pub struct ViewData<'a> {
 }
 
 pub enum ViewAction {
+    /// Replaces every view
     SetView(Box<View>),
-    AddView(Box<View>),
+    /// Like SetView, with the old views transitioning into the new one
+    TransitionTo(Box<View>, Transition),
+    /// Replaces just this view
+    ReplaceSelf(Box<View>),
+    /// Puts a view on top of the stack
+    Push(Box<View>),
     RemoveSelf,
+    /// Removes this view and hands the value to the view below it
+    Return(Box<Any>),
+    /// Removes this view and the given number - 1 views below it
+    Pop(usize),
+    /// Removes every view above the topmost one with this name
+    PopTo(&'static str),
     ExitGame,
 }
 
-pub use self::game::GameView;
+pub use self::difficulty::DifficultyView;
+pub use self::game::{check_levels, Difficulty, EndlessView, GameView, Preset, Range, PRESETS, Score};
+pub use self::game::{GAPS_RANGE, HAZARDS_RANGE, LIVES_RANGE, SPEED_RANGE, TIME_LIMIT_RANGE};
 pub use self::main_menu::MainMenuView;
+pub use self::options::OptionsView;
+pub use self::pause::{PauseView, QuitToMenu, Restart};
+pub use self::results::{GameOverView, LevelCompleteView, NextLevel};
+pub use self::stack::{StackEvent, ViewStack};
+pub use self::transition::{Transition, TransitionKind, ViewTransition};
 
+mod difficulty;
 mod game;
 mod main_menu;
+mod options;
+mod pause;
+mod results;
+mod stack;
+mod transition;
@@ -0,0 +1,321 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use std::any::Any;
+
+use sdl2::pixels::Color;
+
+use context::Context;
+use graphics::{escape_markup, Align, Font, GraphicObject, Sprite, TextStyle};
+use input::{Action, ACTIONS, ACTION_COUNT, Binding, Bindings, Input, InputState};
+use ui::{Anchor, Button, Direction, Focus, Layout, Length, Slot, Widget, WidgetEvent, SCREEN};
+use view::{Difficulty, DifficultyView, View, ViewAction, ViewData, ViewFlags};
+
+pub struct OptionsView {
+    title: Sprite,
+
+    /// One row per action, then Difficulty, Reset Controls and Back
+    rows: Vec<Button>,
+
+    status: Option<Sprite>,
+
+    layout: Layout,
+    title_slot: Slot,
+    row_slots: Vec<Slot>,
+    status_slot: Slot,
+
+    focus: Focus,
+
+    /// The action waiting for the player to press a key
+    binding_action: Option<Action>,
+    /// A press that's already bound elsewhere, waiting to be pressed again to confirm
+    conflicting_binding: Option<Binding>,
+
+    font: Font,
+
+    /// The difficulty view is up over this one
+    in_child_view: bool,
+    /// The difficulty view just closed, so the Difficulty row may be out of date
+    difficulty_changed: bool,
+}
+
+/// Where the rows after the actions are
+const DIFFICULTY_ROW: usize = ACTION_COUNT;
+const RESET_ROW: usize = ACTION_COUNT + 1;
+
+impl OptionsView {
+    pub fn new(context: &mut Context) -> OptionsView {
+        let title_font = context.font_renderer.load_font("assets/fonts/jim_teacher.ttf", context.rel.height(0.12) as u16);
+        let font = context.font_renderer.load_font("assets/fonts/fff_aquarius_bold.ttf", context.rel.height(0.04) as u16);
+
+        let title = match context.font_renderer.render_sprite(context, &title_font, "Options", Color::RGB(255, 255, 255)) {
+            Some(sprite) => sprite,
+            None => panic!("Could not render options title!"),
+        };
+
+        let labels: Vec<&str> = ACTIONS.iter().map(|action| action.name())
+            .chain(vec!["Difficulty", "Reset Controls", "Back"].into_iter())
+            .collect();
+
+        let mut layout = Layout::new();
+
+        let title_slot = layout.add(SCREEN, Anchor::Top, Length::Percent(100.0), Length::Percent(20.0));
+        let status_slot = layout.add(SCREEN, Anchor::Bottom, Length::Percent(100.0), Length::Percent(16.0));
+
+        let list = layout.add(SCREEN, Anchor::Bottom, Length::Percent(60.0), Length::Percent(80.0));
+        layout.set_stack(list, Direction::Vertical, Length::ScreenHeight(1.2));
+
+        let row_slots = labels.iter().map(|_| {
+            layout.add(list, Anchor::Top, Length::Percent(100.0), Length::ScreenHeight(7.0))
+        }).collect::<Vec<Slot>>();
+
+        layout.compute(context.screen_size);
+
+        let rows = labels.iter().zip(row_slots.iter()).map(|(label, slot)| {
+            Button::new(context, &font, label, layout.get(*slot))
+        }).collect();
+
+        let mut options_view = OptionsView {
+            title: title,
+
+            rows: rows,
+
+            status: None,
+
+            layout: layout,
+            title_slot: title_slot,
+            row_slots: row_slots,
+            status_slot: status_slot,
+
+            focus: Focus::new(),
+
+            binding_action: None,
+            conflicting_binding: None,
+
+            font: font,
+
+            in_child_view: false,
+            difficulty_changed: false,
+        };
+
+        options_view.place(context);
+        options_view.refresh_bindings(context);
+        options_view.refresh_difficulty(context);
+        options_view
+    }
+
+    /// Moves everything to where the layout says it goes
+    fn place(&mut self, context: &Context) {
+        self.layout.compute(context.screen_size);
+
+        self.title.transform.position = self.layout.get(self.title_slot).get_center();
+
+        for (row, slot) in self.rows.iter_mut().zip(self.row_slots.iter()) {
+            row.set_region(context, self.layout.get(*slot));
+        }
+
+        if let Some(ref mut status) = self.status {
+            status.transform.position = self.layout.get(self.status_slot).get_center();
+        }
+    }
+
+    fn refresh_bindings(&mut self, context: &Context) {
+        for (index, action) in ACTIONS.iter().enumerate() {
+            let bindings = context.input_translator.get_bindings().get(*action);
+
+            let text = if bindings.is_empty() {
+                "None".to_string()
+            } else {
+                bindings.iter().map(|binding| binding.describe()).collect::<Vec<String>>().join(", ")
+            };
+
+            self.rows[index].set_value(context, Some(&text));
+        }
+    }
+
+    fn refresh_difficulty(&mut self, context: &Context) {
+        let difficulty = Difficulty::load(&context.config);
+
+        let text = if difficulty.adaptive {
+            format!("{}, adaptive", difficulty.preset.name())
+        } else {
+            difficulty.preset.name().to_string()
+        };
+
+        self.rows[DIFFICULTY_ROW].set_value(context, Some(&text));
+    }
+
+    /// Shows a line of markup under the rows
+    fn set_status(&mut self, context: &Context, markup: &str) {
+        let position = self.layout.get(self.status_slot).get_center();
+
+        self.status = context.font_renderer.render_markup(context, &self.font, markup, Color::RGB(255, 255, 255), &TextStyle::new(Align::Center)).map(|mut sprite| {
+            sprite.transform.position = position;
+            sprite
+        });
+    }
+
+    fn save_bindings(&mut self, context: &mut Context) {
+        context.input_translator.get_bindings().save(&mut context.config);
+        context.save_config();
+
+        self.refresh_bindings(context);
+    }
+
+    /// Handles a press while waiting for a binding
+    fn bind(&mut self, context: &mut Context, action: Action, binding: Binding) {
+        let conflict = match context.input_translator.get_bindings().action_for(binding) {
+            Some(other) if other != action => Some(other),
+            _ => None,
+        };
+
+        if let Some(other) = conflict {
+            if self.conflicting_binding != Some(binding) {
+                self.conflicting_binding = Some(binding);
+                self.set_status(context, &format!(
+                    "[color=#ff0]{}[/color] is bound to {}. Press it again to move it.",
+                    escape_markup(&binding.describe()),
+                    other.name()
+                ));
+                context.input_translator.capture_binding();
+                return;
+            }
+        }
+
+        context.input_translator.get_bindings_mut().bind(action, binding);
+        self.save_bindings(context);
+
+        self.binding_action = None;
+        self.conflicting_binding = None;
+        self.set_status(context, &format!("Bound [color=#ff0]{}[/color] to {}", escape_markup(&binding.describe()), action.name()));
+    }
+}
+
+impl View for OptionsView {
+    fn get_name(&self) -> &'static str {
+        "OptionsView"
+    }
+
+    fn get_view_data(&self) -> ViewData {
+        if self.in_child_view {
+            return ViewData {
+                graphic_objects: Vec::new(),
+            };
+        }
+
+        let mut graphic_objects = vec![
+            &self.title as &GraphicObject,
+        ];
+
+        graphic_objects.extend(self.rows.iter().map(|row| row as &GraphicObject));
+
+        if let Some(ref status) = self.status {
+            graphic_objects.push(status);
+        }
+
+        ViewData {
+            graphic_objects: graphic_objects,
+        }
+    }
+
+    fn update(&mut self, context: &mut Context, input: Vec<Input>, state: &InputState, _: i64) -> (Option<ViewAction>, Vec<Input>) {
+        if self.in_child_view {
+            return (None, Vec::new());
+        }
+
+        if self.difficulty_changed {
+            self.difficulty_changed = false;
+            self.refresh_difficulty(context);
+        }
+
+        if let Some(action) = self.binding_action {
+            for i in input.iter() {
+                match *i {
+                    Input::Bind(binding) => {
+                        self.bind(context, action, binding);
+                        break;
+                    },
+                    Input::Pause => {
+                        self.binding_action = None;
+                        self.conflicting_binding = None;
+                        self.set_status(context, "Cancelled");
+                        break;
+                    },
+                    _ => (),
+                }
+            }
+
+            return (None, Vec::new());
+        }
+
+        let (event, unused) = self.focus.update(
+            context,
+            &mut self.rows.iter_mut().map(|row| row as &mut Widget).collect(),
+            &input,
+            state,
+        );
+
+        // Returning lets the main menu know we're gone
+        if unused.iter().find(|input| **input == Input::Pause).is_some() {
+            return (Some(ViewAction::Return(Box::new(()))), Vec::new());
+        }
+
+        match event {
+            Some((index, WidgetEvent::Pressed)) if index < ACTION_COUNT => {
+                let action = ACTIONS[index];
+
+                self.binding_action = Some(action);
+                context.input_translator.capture_binding();
+                self.set_status(context, &format!("Press a key or button for {}, or Escape to cancel", action.name()));
+            },
+            Some((DIFFICULTY_ROW, WidgetEvent::Pressed)) => {
+                self.in_child_view = true;
+                return (Some(ViewAction::Push(Box::new(DifficultyView::new(context)))), Vec::new());
+            },
+            Some((RESET_ROW, WidgetEvent::Pressed)) => {
+                *context.input_translator.get_bindings_mut() = Bindings::default();
+                self.save_bindings(context);
+                self.set_status(context, "Controls reset to defaults");
+            },
+            Some((_, WidgetEvent::Pressed)) => {
+                return (Some(ViewAction::Return(Box::new(()))), Vec::new());
+            },
+            _ => (),
+        }
+
+        (None, Vec::new())
+    }
+
+    fn get_flags(&self) -> ViewFlags {
+        // The main menu's backdrop shows through, but its buttons mustn't react
+        ViewFlags {
+            blocks_input_below: true,
+            ..ViewFlags::default()
+        }
+    }
+
+    fn resize(&mut self, context: &mut Context) {
+        self.place(context);
+    }
+
+    fn child_returned(&mut self, _: Box<Any>) {
+        self.in_child_view = false;
+        self.difficulty_changed = true;
+    }
+}
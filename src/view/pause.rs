@@ -0,0 +1,195 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use std::any::Any;
+
+use sdl2::pixels::Color;
+
+use context::Context;
+use graphics::{Align, GraphicObject};
+use input::{Input, InputState};
+use math::{Rectangle, Vector2};
+use ui::{Anchor, Button, Direction, Focus, Label, Layout, Length, Panel, Slot, Widget, WidgetEvent, SCREEN};
+use view::{OptionsView, View, ViewAction, ViewData, ViewFlags};
+
+/// Returned to the game view to save the run and go back to the main menu
+pub struct QuitToMenu;
+
+/// Returned to the game view to start a fresh run of the same mode
+pub struct Restart;
+
+/// Pushed over the game.  The game clock is frozen for as long as it's up,
+/// and the game shows through dimmed.
+pub struct PauseView {
+    dim: Panel,
+    title: Label,
+
+    resume_button: Button,
+    restart_button: Button,
+    options_button: Button,
+    quit_button: Button,
+
+    layout: Layout,
+    title_slot: Slot,
+    button_slots: Vec<Slot>,
+
+    focus: Focus,
+    in_child_view: bool,
+}
+
+impl PauseView {
+    pub fn new(context: &mut Context) -> PauseView {
+        context.clock.freeze();
+
+        let title_font = context.font_renderer.load_font("assets/fonts/fff_aquarius_bold.ttf", context.rel.height(0.1) as u16);
+        let menu_font = context.font_renderer.load_font("assets/fonts/fff_aquarius_bold.ttf", context.rel.height(0.06) as u16);
+
+        let mut layout = Layout::new();
+
+        let title_slot = layout.add(SCREEN, Anchor::Top, Length::Percent(100.0), Length::Percent(30.0));
+
+        let menu = layout.add(SCREEN, Anchor::Bottom, Length::Percent(50.0), Length::Percent(65.0));
+        layout.set_stack(menu, Direction::Vertical, Length::ScreenHeight(2.2));
+
+        let button_slots = (0..4).map(|_| {
+            layout.add(menu, Anchor::Top, Length::Percent(100.0), Length::ScreenHeight(10.0))
+        }).collect::<Vec<Slot>>();
+
+        layout.compute(context.screen_size);
+
+        let mut pause_view = PauseView {
+            dim: Panel::new(screen_region(context), Color::RGBA(0, 0, 0, 160)),
+            title: Label::new(context, &title_font, "Paused", layout.get(title_slot), Align::Center),
+
+            resume_button: Button::new(context, &menu_font, "Resume", layout.get(button_slots[0])),
+            restart_button: Button::new(context, &menu_font, "Restart", layout.get(button_slots[1])),
+            options_button: Button::new(context, &menu_font, "Options", layout.get(button_slots[2])),
+            quit_button: Button::new(context, &menu_font, "Quit to Menu", layout.get(button_slots[3])),
+
+            layout: layout,
+            title_slot: title_slot,
+            button_slots: button_slots,
+
+            focus: Focus::new(),
+            in_child_view: false,
+        };
+
+        pause_view.place(context);
+        pause_view
+    }
+
+    /// Moves everything to where the layout says it goes
+    fn place(&mut self, context: &Context) {
+        self.layout.compute(context.screen_size);
+
+        self.dim.set_region(context, screen_region(context));
+        self.title.set_region(context, self.layout.get(self.title_slot));
+
+        self.resume_button.set_region(context, self.layout.get(self.button_slots[0]));
+        self.restart_button.set_region(context, self.layout.get(self.button_slots[1]));
+        self.options_button.set_region(context, self.layout.get(self.button_slots[2]));
+        self.quit_button.set_region(context, self.layout.get(self.button_slots[3]));
+    }
+}
+
+fn screen_region(context: &Context) -> Rectangle {
+    Rectangle::new(Vector2::zero(), context.screen_size).unwrap()
+}
+
+impl View for PauseView {
+    fn get_name(&self) -> &'static str {
+        "PauseView"
+    }
+
+    fn get_view_data(&self) -> ViewData {
+        let mut graphic_objects = vec![
+            &self.dim as &GraphicObject,
+        ];
+
+        if !self.in_child_view {
+            graphic_objects.extend(vec![
+                &self.title as &GraphicObject,
+
+                &self.resume_button,
+                &self.restart_button,
+                &self.options_button,
+                &self.quit_button,
+            ].iter());
+        }
+
+        ViewData {
+            graphic_objects: graphic_objects,
+        }
+    }
+
+    fn update(&mut self, context: &mut Context, input: Vec<Input>, state: &InputState, _: i64) -> (Option<ViewAction>, Vec<Input>) {
+        if self.in_child_view {
+            return (None, Vec::new());
+        }
+
+        let (event, unused) = self.focus.update(context, &mut vec![
+            &mut self.resume_button as &mut Widget,
+            &mut self.restart_button,
+            &mut self.options_button,
+            &mut self.quit_button,
+        ], &input, state);
+
+        if unused.iter().find(|input| **input == Input::Pause).is_some() {
+            context.clock.thaw();
+            return (Some(ViewAction::RemoveSelf), Vec::new());
+        }
+
+        let view_action = match event {
+            Some((0, WidgetEvent::Pressed)) => {
+                context.clock.thaw();
+                Some(ViewAction::RemoveSelf)
+            },
+            Some((1, WidgetEvent::Pressed)) => {
+                context.clock.thaw();
+                Some(ViewAction::Return(Box::new(Restart)))
+            },
+            Some((2, WidgetEvent::Pressed)) => {
+                self.in_child_view = true;
+                Some(ViewAction::Push(Box::new(OptionsView::new(context))))
+            },
+            Some((3, WidgetEvent::Pressed)) => {
+                context.clock.thaw();
+                Some(ViewAction::Return(Box::new(QuitToMenu)))
+            },
+            _ => None,
+        };
+
+        (view_action, Vec::new())
+    }
+
+    fn resize(&mut self, context: &mut Context) {
+        self.place(context);
+    }
+
+    fn get_flags(&self) -> ViewFlags {
+        ViewFlags {
+            blocks_input_below: true,
+            ..ViewFlags::default()
+        }
+    }
+
+    fn child_returned(&mut self, _: Box<Any>) {
+        self.in_child_view = false;
+    }
+}
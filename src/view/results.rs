@@ -0,0 +1,492 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use sdl2::pixels::Color;
+
+use context::Context;
+use graphics::{Align, Font, GraphicObject, Text};
+use input::{Input, InputState};
+use math::{Rectangle, Vector2};
+use ui::{Anchor, Button, Direction, Focus, Label, Layout, Length, Panel, Slot, TextField, TextFieldEvent, Widget, WidgetEvent, SCREEN};
+use view::{QuitToMenu, Restart, Score, View, ViewAction, ViewData, ViewFlags};
+
+/// How long each row of the tally takes to count up
+const TALLY_ROW_NS: i64 = 600_000_000;
+/// Blink period of the new high score notice
+const HIGH_SCORE_BLINK_NS: i64 = 400_000_000;
+/// Initials, arcade style
+const NAME_LENGTH: usize = 3;
+
+const TALLY_TEXT: Color = Color::RGB(255, 255, 255);
+const HIGH_SCORE_TEXT: Color = Color::RGB(255, 220, 40);
+
+/// Returned to the GameView by LevelCompleteView to go on to the next level
+pub struct NextLevel;
+
+/// Shown over the game when the last life is lost.  Retry returns Restart
+/// and Main Menu returns QuitToMenu, for whichever game view is below.
+pub struct GameOverView {
+    results: Results,
+}
+
+impl GameOverView {
+    /// The breakdown is the whole run's.  Each mode keeps its own high score
+    /// in the config under high_score_key.
+    pub fn new(context: &mut Context, title: &str, breakdown: Vec<(String, u32)>, high_score_key: &str, high_score: u32) -> GameOverView {
+        let total = breakdown.iter().map(|&(_, points)| points).sum::<u32>();
+
+        let mut results = Results::new(context, title, breakdown, total, high_score_key, high_score, &["Retry", "Main Menu"]);
+        results.ask_name(context);
+
+        GameOverView {
+            results: results,
+        }
+    }
+}
+
+impl View for GameOverView {
+    fn get_name(&self) -> &'static str {
+        "GameOverView"
+    }
+
+    fn get_view_data(&self) -> ViewData {
+        ViewData {
+            graphic_objects: self.results.get_graphic_objects(),
+        }
+    }
+
+    fn update(&mut self, context: &mut Context, input: Vec<Input>, state: &InputState, _: i64) -> (Option<ViewAction>, Vec<Input>) {
+        let view_action = match self.results.update(context, &input, state) {
+            Some(0) => {
+                context.clock.thaw();
+                Some(ViewAction::Return(Box::new(Restart)))
+            },
+            Some(_) => {
+                context.clock.thaw();
+                Some(ViewAction::Return(Box::new(QuitToMenu)))
+            },
+            None => None,
+        };
+
+        (view_action, Vec::new())
+    }
+
+    fn resize(&mut self, context: &mut Context) {
+        self.results.place(context);
+    }
+
+    fn get_flags(&self) -> ViewFlags {
+        self.results.get_flags()
+    }
+}
+
+/// Shown over the game when every home is filled
+pub struct LevelCompleteView {
+    results: Results,
+}
+
+impl LevelCompleteView {
+    /// level_score is just this level's; run_total is the whole run's so far
+    pub fn new(context: &mut Context, level_score: Score, run_total: u32, level: u32, high_score: u32) -> LevelCompleteView {
+        let title = format!("Level {} Complete", level);
+
+        LevelCompleteView {
+            results: Results::new(context, &title, level_score.get_breakdown(), run_total, "high_score", high_score, &["Continue", "Main Menu"]),
+        }
+    }
+}
+
+impl View for LevelCompleteView {
+    fn get_name(&self) -> &'static str {
+        "LevelCompleteView"
+    }
+
+    fn get_view_data(&self) -> ViewData {
+        ViewData {
+            graphic_objects: self.results.get_graphic_objects(),
+        }
+    }
+
+    fn update(&mut self, context: &mut Context, input: Vec<Input>, state: &InputState, _: i64) -> (Option<ViewAction>, Vec<Input>) {
+        let view_action = match self.results.update(context, &input, state) {
+            Some(0) => {
+                context.clock.thaw();
+                Some(ViewAction::Return(Box::new(NextLevel)))
+            },
+            Some(_) => {
+                context.clock.thaw();
+                Some(ViewAction::Return(Box::new(QuitToMenu)))
+            },
+            None => None,
+        };
+
+        (view_action, Vec::new())
+    }
+
+    fn resize(&mut self, context: &mut Context) {
+        self.results.place(context);
+    }
+
+    fn get_flags(&self) -> ViewFlags {
+        self.results.get_flags()
+    }
+}
+
+/// What the two results screens have in common: the game dimmed behind a
+/// title, a score tally and some buttons.  The game clock is frozen while
+/// they're up.
+struct Results {
+    dim: Panel,
+    title: Label,
+    tally: Tally,
+    high_score: Option<Text>,
+    /// Who holds the high score, when it wasn't beaten
+    holder: Option<Text>,
+    /// Where a new high scorer types their initials
+    name_field: Option<TextField>,
+    buttons: Vec<Button>,
+
+    layout: Layout,
+    title_slot: Slot,
+    tally_slot: Slot,
+    high_score_slot: Slot,
+    button_slots: Vec<Slot>,
+
+    focus: Focus,
+    blink_ns: i64,
+    high_score_key: String,
+}
+
+impl Results {
+    /// The tally counts up the breakdown, with run_total checked against the
+    /// high score from when the run started
+    fn new(context: &mut Context, title: &str, breakdown: Vec<(String, u32)>, run_total: u32, high_score_key: &str, high_score: u32, buttons: &[&str]) -> Results {
+        context.clock.freeze();
+
+        let title_font = context.font_renderer.load_font("assets/fonts/fff_aquarius_bold.ttf", context.rel.height(0.08) as u16);
+        let tally_font = context.font_renderer.load_font("assets/fonts/fff_aquarius_bold.ttf", context.rel.height(0.045) as u16);
+        let menu_font = context.font_renderer.load_font("assets/fonts/fff_aquarius_bold.ttf", context.rel.height(0.06) as u16);
+
+        let new_high_score = run_total > high_score;
+        if run_total > context.config.get_or(high_score_key, 0) {
+            context.config.set(high_score_key, run_total);
+            context.save_config();
+        }
+
+        let holder = context.config.get(&name_key(high_score_key)).map(|name| {
+            format!("High score {} by {}", context.config.get_or(high_score_key, 0), name)
+        });
+
+        let mut layout = Layout::new();
+
+        let title_slot = layout.add(SCREEN, Anchor::Top, Length::Percent(100.0), Length::Percent(18.0));
+        let tally_slot = layout.add(SCREEN, Anchor::Center, Length::Percent(50.0), Length::Percent(40.0));
+        let high_score_slot = layout.add(SCREEN, Anchor::Bottom, Length::Percent(100.0), Length::Percent(36.0));
+
+        let menu = layout.add(SCREEN, Anchor::Bottom, Length::Percent(50.0), Length::Percent(28.0));
+        layout.set_stack(menu, Direction::Vertical, Length::ScreenHeight(1.5));
+
+        let button_slots = buttons.iter().map(|_| {
+            layout.add(menu, Anchor::Top, Length::Percent(100.0), Length::ScreenHeight(9.0))
+        }).collect::<Vec<Slot>>();
+
+        layout.compute(context.screen_size);
+
+        let mut results = Results {
+            dim: Panel::new(screen_region(context), Color::RGBA(0, 0, 0, 170)),
+            title: Label::new(context, &title_font, title, layout.get(title_slot), Align::Center),
+            tally: Tally::new(&tally_font, breakdown),
+            high_score: if new_high_score {
+                let mut text = Text::new(&tally_font, "New high score!", HIGH_SCORE_TEXT);
+                text.align = Align::Center;
+                Some(text)
+            } else {
+                None
+            },
+            holder: match holder {
+                Some(ref holder) if !new_high_score => {
+                    let mut text = Text::new(&tally_font, holder, TALLY_TEXT);
+                    text.align = Align::Center;
+                    Some(text)
+                },
+                _ => None,
+            },
+            name_field: None,
+            buttons: buttons.iter().zip(button_slots.iter()).map(|(text, &slot)| {
+                Button::new(context, &menu_font, text, layout.get(slot))
+            }).collect(),
+
+            layout: layout,
+            title_slot: title_slot,
+            tally_slot: tally_slot,
+            high_score_slot: high_score_slot,
+            button_slots: button_slots,
+
+            focus: Focus::new(),
+            blink_ns: 0,
+            high_score_key: high_score_key.to_string(),
+        };
+
+        results.place(context);
+        results
+    }
+
+    /// Moves everything to where the layout says it goes
+    fn place(&mut self, context: &Context) {
+        self.layout.compute(context.screen_size);
+
+        self.dim.set_region(context, screen_region(context));
+        self.title.set_region(context, self.layout.get(self.title_slot));
+        self.tally.place(self.layout.get(self.tally_slot));
+
+        let region = self.layout.get(self.high_score_slot);
+        for text in self.high_score.iter_mut().chain(self.holder.iter_mut()) {
+            text.position = Vector2::new(region.get_center().x, region.position.y);
+        }
+
+        if let Some(ref mut name_field) = self.name_field {
+            name_field.set_region(context, name_region(context, region));
+        }
+
+        for (button, &slot) in self.buttons.iter_mut().zip(self.button_slots.iter()) {
+            button.set_region(context, self.layout.get(slot));
+        }
+    }
+
+    /// After the tally, a new high scorer types their initials before the
+    /// buttons come up
+    fn ask_name(&mut self, context: &mut Context) {
+        if self.high_score.is_some() {
+            let font = context.font_renderer.load_font("assets/fonts/fff_aquarius_bold.ttf", context.rel.height(0.045) as u16);
+            let region = name_region(context, self.layout.get(self.high_score_slot));
+            self.name_field = Some(TextField::new(context, &font, region, NAME_LENGTH));
+        }
+    }
+
+    fn get_graphic_objects(&self) -> Vec<&GraphicObject> {
+        let mut graphic_objects = vec![
+            &self.dim as &GraphicObject,
+            &self.title,
+            &self.tally,
+        ];
+
+        if let Some(ref high_score) = self.high_score {
+            if self.tally.is_finished() && self.blink_ns < HIGH_SCORE_BLINK_NS {
+                graphic_objects.push(high_score);
+            }
+        }
+
+        if let Some(ref holder) = self.holder {
+            if self.tally.is_finished() {
+                graphic_objects.push(holder);
+            }
+        }
+
+        match self.name_field {
+            Some(ref name_field) if self.tally.is_finished() => graphic_objects.push(name_field),
+            Some(_) => {},
+            None => graphic_objects.extend(self.buttons.iter().map(|button| button as &GraphicObject)),
+        }
+        graphic_objects
+    }
+
+    /// Returns the index of the button pressed, if any.  Enter or a click
+    /// while the tally is still counting skips to the end instead.
+    fn update(&mut self, context: &mut Context, input: &Vec<Input>, state: &InputState) -> Option<usize> {
+        // The game clock is frozen, so go by real time
+        let elapsed_ns = context.clock.real_elapsed_ns();
+        self.blink_ns = (self.blink_ns + elapsed_ns) % (2 * HIGH_SCORE_BLINK_NS);
+
+        if !self.tally.is_finished() {
+            let skip = input.iter().any(|input| match *input {
+                Input::Enter | Input::PointerDown(_) => true,
+                _ => false,
+            });
+
+            if skip {
+                self.tally.finish();
+            } else {
+                self.tally.update(elapsed_ns);
+            }
+            return None;
+        }
+
+        if self.name_field.is_some() {
+            self.update_name(context, input);
+            return None;
+        }
+
+        let (event, _) = self.focus.update(
+            context,
+            &mut self.buttons.iter_mut().map(|button| button as &mut Widget).collect(),
+            input,
+            state,
+        );
+
+        match event {
+            Some((index, WidgetEvent::Pressed)) => Some(index),
+            _ => None,
+        }
+    }
+
+    fn update_name(&mut self, context: &mut Context, input: &Vec<Input>) {
+        if !context.input_translator.is_text_mode() {
+            context.start_text_input();
+        }
+
+        let event = match self.name_field {
+            Some(ref mut name_field) => name_field.update(context, input),
+            None => return,
+        };
+
+        match event {
+            Some(TextFieldEvent::Committed) => {
+                let name = self.name_field.as_ref().map(|name_field| name_field.get_text().trim().to_uppercase()).unwrap_or(String::new());
+                if !name.is_empty() {
+                    context.config.set(&name_key(&self.high_score_key), name);
+                    context.save_config();
+                }
+            },
+            Some(TextFieldEvent::Cancelled) => {},
+            None => return,
+        }
+
+        context.stop_text_input();
+        self.name_field = None;
+    }
+
+    fn get_flags(&self) -> ViewFlags {
+        ViewFlags {
+            blocks_input_below: true,
+            ..ViewFlags::default()
+        }
+    }
+}
+
+fn screen_region(context: &Context) -> Rectangle {
+    Rectangle::new(Vector2::zero(), context.screen_size).unwrap()
+}
+
+/// Just under the new high score notice
+fn name_region(context: &Context, high_score_region: Rectangle) -> Rectangle {
+    let size = Vector2::new(context.rel.height(0.2), context.rel.height(0.06));
+    Rectangle::new(
+        Vector2::new(high_score_region.get_center().x - size.x / 2.0, high_score_region.position.y + context.rel.height(0.04)),
+        size,
+    ).unwrap()
+}
+
+/// Where the holder of the high score under high_score_key is kept
+fn name_key(high_score_key: &str) -> String {
+    format!("{}_name", high_score_key)
+}
+
+/// Rows of points that count up one after another, then a total
+struct Tally {
+    rows: Vec<TallyRow>,
+    total: TallyRow,
+
+    elapsed_ns: i64,
+}
+
+struct TallyRow {
+    label: Text,
+    value: Text,
+    points: u32,
+}
+
+impl TallyRow {
+    fn new(font: &Font, label: &str, points: u32) -> TallyRow {
+        let mut label = Text::new(font, label, TALLY_TEXT);
+        label.align = Align::Left;
+
+        let mut value = Text::new(font, "0", TALLY_TEXT);
+        value.align = Align::Right;
+
+        TallyRow {
+            label: label,
+            value: value,
+            points: points,
+        }
+    }
+
+    fn show(&mut self, points: u32) {
+        let points = points.to_string();
+        if self.value.get_text() != points {
+            self.value.set_text(&points);
+        }
+    }
+}
+
+impl Tally {
+    fn new(font: &Font, breakdown: Vec<(String, u32)>) -> Tally {
+        let total = breakdown.iter().map(|&(_, points)| points).sum::<u32>();
+
+        Tally {
+            rows: breakdown.iter().map(|&(ref label, points)| TallyRow::new(font, label, points)).collect(),
+            total: TallyRow::new(font, "Total", total),
+
+            elapsed_ns: 0,
+        }
+    }
+
+    fn place(&mut self, region: Rectangle) {
+        let line = region.get_size().y / (self.rows.len() + 1) as f32;
+        let left = region.position.x;
+        let right = region.position.x + region.get_size().x;
+
+        for (index, row) in self.rows.iter_mut().chain(Some(&mut self.total)).enumerate() {
+            let y = region.position.y + line * (index as f32 + 0.5);
+            row.label.position = Vector2::new(left, y);
+            row.value.position = Vector2::new(right, y);
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed_ns >= TALLY_ROW_NS * self.rows.len() as i64
+    }
+
+    fn finish(&mut self) {
+        self.update(TALLY_ROW_NS * self.rows.len() as i64);
+    }
+
+    fn update(&mut self, elapsed_ns: i64) {
+        self.elapsed_ns = (self.elapsed_ns + elapsed_ns).min(TALLY_ROW_NS * self.rows.len() as i64);
+
+        let mut counted = 0;
+        for (index, row) in self.rows.iter_mut().enumerate() {
+            let progress = (self.elapsed_ns - TALLY_ROW_NS * index as i64) as f32 / TALLY_ROW_NS as f32;
+            let points = (row.points as f32 * progress.max(0.0).min(1.0)).round() as u32;
+
+            row.show(points);
+            counted += points;
+        }
+
+        self.total.show(counted);
+    }
+}
+
+impl GraphicObject for Tally {
+    fn draw(&self, context: &mut Context) {
+        for row in self.rows.iter().chain(Some(&self.total)) {
+            row.label.draw(context);
+            row.value.draw(context);
+        }
+    }
+}
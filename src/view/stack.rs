@@ -0,0 +1,363 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use std::any::Any;
+use std::mem;
+
+use context::Context;
+use input::{Input, InputState};
+use view::{Transition, View, ViewAction};
+
+/// Something the stack can't handle by itself
+pub enum StackEvent {
+    /// The stack now only holds the new view; the old views are handed back
+    /// so they can be drawn while the transition runs.
+    Transition(Transition, ViewStack),
+    Exit,
+}
+
+/// What to update after a view's action has been applied
+enum Step {
+    Next(usize),
+    Stop,
+    Event(StackEvent),
+}
+
+/// The views on screen, topmost first.  Views are updated from the top down,
+/// each getting the input the one above passed on, and drawn bottom up.
+pub struct ViewStack {
+    views: Vec<Box<View>>,
+}
+
+impl ViewStack {
+    pub fn new(view: Box<View>) -> ViewStack {
+        ViewStack {
+            views: vec![view],
+        }
+    }
+
+    /// Topmost first
+    pub fn get_names(&self) -> Vec<&'static str> {
+        self.views.iter().map(|view| view.get_name()).collect()
+    }
+
+    /// The views that show, topmost first.  Views under one that doesn't
+    /// render below it are left out.
+    pub fn get_visible(&self) -> &[Box<View>] {
+        match self.views.iter().position(|view| !view.get_flags().renders_below) {
+            Some(index) => &self.views[..index + 1],
+            None => &self.views,
+        }
+    }
+
+    pub fn resize(&mut self, context: &mut Context) {
+        for view in self.views.iter_mut() {
+            view.resize(context);
+        }
+    }
+
+    /// Lets every view know the game is closing
+    pub fn exit(&mut self, context: &mut Context) {
+        for view in self.views.iter_mut() {
+            view.exit(context);
+        }
+    }
+
+    pub fn update(&mut self, context: &mut Context, input: Vec<Input>, state: &InputState, elapsed_ns: i64) -> Option<StackEvent> {
+        self.run(input, |view, input| view.update(context, input, state, elapsed_ns))
+    }
+
+    /// Goes down the stack, updating each view with update and applying its
+    /// action.  Kept apart from update so it can be tested without SDL.
+    fn run<F>(&mut self, input: Vec<Input>, mut update: F) -> Option<StackEvent>
+        where F: FnMut(&mut Box<View>, Vec<Input>) -> (Option<ViewAction>, Vec<Input>)
+    {
+        let mut input = input;
+        let mut index = 0;
+
+        while index < self.views.len() {
+            let (action, pass_input) = update(&mut self.views[index], input);
+
+            input = if self.views[index].get_flags().blocks_input_below {
+                Vec::new()
+            } else {
+                pass_input
+            };
+
+            index = match action {
+                Some(action) => match self.apply(index, action) {
+                    Step::Next(next) => next,
+                    Step::Stop => break,
+                    Step::Event(event) => return Some(event),
+                },
+                None => index + 1,
+            };
+        }
+
+        None
+    }
+
+    /// Applies an action returned by the view at index.  This doesn't touch
+    /// the views beyond moving them around, so it works without SDL.
+    fn apply(&mut self, index: usize, action: ViewAction) -> Step {
+        match action {
+            ViewAction::SetView(view) => {
+                self.views.clear();
+                self.views.push(view);
+                Step::Stop
+            },
+            ViewAction::TransitionTo(view, transition) => {
+                let outgoing = mem::replace(&mut self.views, vec![view]);
+                Step::Event(StackEvent::Transition(transition, ViewStack { views: outgoing }))
+            },
+            ViewAction::ReplaceSelf(view) => {
+                self.views[index] = view;
+                Step::Next(index + 1)
+            },
+            ViewAction::Push(view) => {
+                self.views.insert(0, view);
+                Step::Next(index + 2)
+            },
+            ViewAction::RemoveSelf => {
+                self.views.remove(index);
+                Step::Next(index)
+            },
+            ViewAction::Return(value) => {
+                self.views.remove(index);
+                self.deliver(index, value);
+                Step::Next(index)
+            },
+            ViewAction::Pop(count) => {
+                // The view and count - 1 views below it
+                let end = (index + count).min(self.views.len());
+                self.views.drain(index..end);
+                Step::Next(index)
+            },
+            ViewAction::PopTo(name) => {
+                match self.views.iter().skip(index).position(|view| view.get_name() == name) {
+                    Some(offset) => {
+                        self.views.drain(..index + offset);
+                    },
+                    None => warn!("Can't pop to {}; it isn't on the stack", name),
+                }
+                Step::Stop
+            },
+            ViewAction::ExitGame => Step::Event(StackEvent::Exit),
+        }
+    }
+
+    /// Hands a popped view's value to the view that was under it
+    fn deliver(&mut self, index: usize, value: Box<Any>) {
+        match self.views.get_mut(index) {
+            Some(parent) => parent.child_returned(value),
+            None => warn!("A view returned a value with nothing under it"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    use context::Context;
+    use input::{Input, InputState};
+    use view::{View, ViewAction, ViewData, ViewFlags};
+    use super::{StackEvent, Step, ViewStack};
+
+    /// Only ever updated through ViewStack::run, which the tests script
+    struct StubView {
+        name: &'static str,
+        flags: ViewFlags,
+        returned: Rc<RefCell<Vec<u32>>>,
+    }
+
+    impl View for StubView {
+        fn get_name(&self) -> &'static str {
+            self.name
+        }
+
+        fn get_view_data(&self) -> ViewData {
+            ViewData {
+                graphic_objects: Vec::new(),
+            }
+        }
+
+        fn update(&mut self, _: &mut Context, _: Vec<Input>, _: &InputState, _: i64) -> (Option<ViewAction>, Vec<Input>) {
+            unreachable!()
+        }
+
+        fn get_flags(&self) -> ViewFlags {
+            self.flags
+        }
+
+        fn child_returned(&mut self, value: Box<Any>) {
+            if let Ok(value) = value.downcast::<u32>() {
+                self.returned.borrow_mut().push(*value);
+            }
+        }
+    }
+
+    fn stub(name: &'static str) -> Box<View> {
+        stub_with(name, ViewFlags::default(), Rc::new(RefCell::new(Vec::new())))
+    }
+
+    fn stub_with(name: &'static str, flags: ViewFlags, returned: Rc<RefCell<Vec<u32>>>) -> Box<View> {
+        Box::new(StubView {
+            name: name,
+            flags: flags,
+            returned: returned,
+        })
+    }
+
+    /// Bottom view first, to read like building the stack up
+    fn stack(names: &[&'static str]) -> ViewStack {
+        ViewStack {
+            views: names.iter().rev().map(|name| stub(name)).collect(),
+        }
+    }
+
+    fn is_next(step: Step, expected: usize) -> bool {
+        match step {
+            Step::Next(index) => index == expected,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn push_then_return_delivers_to_the_view_below() {
+        let returned = Rc::new(RefCell::new(Vec::new()));
+        let mut views = ViewStack::new(stub_with("game", ViewFlags::default(), returned.clone()));
+
+        assert!(is_next(views.apply(0, ViewAction::Push(stub("pause"))), 2));
+        assert_eq!(views.get_names(), vec!["pause", "game"]);
+
+        assert!(is_next(views.apply(0, ViewAction::Return(Box::new(7u32))), 0));
+        assert_eq!(views.get_names(), vec!["game"]);
+        assert_eq!(*returned.borrow(), vec![7]);
+    }
+
+    #[test]
+    fn pop_removes_the_view_and_those_below() {
+        let mut views = stack(&["menu", "game", "pause", "confirm"]);
+
+        assert!(is_next(views.apply(1, ViewAction::Pop(2)), 1));
+        assert_eq!(views.get_names(), vec!["confirm", "menu"]);
+
+        // Popping past the bottom just empties the stack
+        views.apply(0, ViewAction::Pop(5));
+        assert!(views.get_names().is_empty());
+    }
+
+    #[test]
+    fn pop_to_keeps_the_named_view() {
+        let mut views = stack(&["menu", "game", "pause", "confirm"]);
+
+        match views.apply(0, ViewAction::PopTo("game")) {
+            Step::Stop => {},
+            _ => panic!("PopTo should stop the update"),
+        }
+        assert_eq!(views.get_names(), vec!["game", "menu"]);
+
+        // An unknown name leaves the stack alone
+        views.apply(0, ViewAction::PopTo("options"));
+        assert_eq!(views.get_names(), vec!["game", "menu"]);
+    }
+
+    #[test]
+    fn replace_self_swaps_just_that_view() {
+        let mut views = stack(&["menu", "game"]);
+
+        assert!(is_next(views.apply(0, ViewAction::ReplaceSelf(stub("results"))), 1));
+        assert_eq!(views.get_names(), vec!["results", "menu"]);
+    }
+
+    #[test]
+    fn exit_game_is_handed_up() {
+        let mut views = stack(&["menu"]);
+
+        match views.apply(0, ViewAction::ExitGame) {
+            Step::Event(StackEvent::Exit) => {},
+            _ => panic!("ExitGame should become StackEvent::Exit"),
+        }
+        assert_eq!(views.get_names().len(), 1);
+    }
+
+    #[test]
+    fn renders_below_decides_what_is_visible() {
+        let opaque = ViewFlags {
+            renders_below: false,
+            ..ViewFlags::default()
+        };
+        let returned = Rc::new(RefCell::new(Vec::new()));
+
+        let mut views = stack(&["menu"]);
+        views.views.insert(0, stub_with("game", opaque, returned));
+        views.views.insert(0, stub("pause"));
+
+        let visible: Vec<&str> = views.get_visible().iter().map(|view| view.get_name()).collect();
+        assert_eq!(visible, vec!["pause", "game"]);
+    }
+
+    #[test]
+    fn blocks_input_below_stops_input_going_down() {
+        let blocking = ViewFlags {
+            blocks_input_below: true,
+            ..ViewFlags::default()
+        };
+        let returned = Rc::new(RefCell::new(Vec::new()));
+
+        let mut views = stack(&["menu", "game"]);
+        views.views.insert(0, stub_with("pause", blocking, returned));
+
+        let mut seen = HashMap::new();
+        let event = views.run(vec![Input::Up], |view, input| {
+            seen.insert(view.get_name(), input.len());
+            (None, input)
+        });
+
+        assert!(event.is_none());
+        assert_eq!(seen["pause"], 1);
+        assert_eq!(seen["game"], 0);
+        assert_eq!(seen["menu"], 0);
+    }
+
+    #[test]
+    fn run_passes_on_input_and_skips_pushed_views() {
+        let mut views = stack(&["menu", "game"]);
+
+        let mut updated = Vec::new();
+        views.run(vec![Input::Up, Input::Down], |view, input| {
+            updated.push(view.get_name());
+
+            if view.get_name() == "game" {
+                // Pushed views wait for the next update
+                (Some(ViewAction::Push(stub("pause"))), input.into_iter().filter(|i| *i == Input::Down).collect())
+            } else {
+                assert!(input == vec![Input::Down]);
+                (None, input)
+            }
+        });
+
+        assert_eq!(updated, vec!["game", "menu"]);
+        assert_eq!(views.get_names(), vec!["pause", "game", "menu"]);
+    }
+}
@@ -0,0 +1,213 @@
+//
+// This file is part of The Reaping.
+//
+// The Reaping is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// The Reaping is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with The Reaping. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright 2016 Chris Foster
+//
+
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect as SdlRectangle;
+use sdl2::render::{BlendMode, Texture};
+
+use context::Context;
+use graphics;
+use math::{Rectangle, Vector2};
+use view::ViewStack;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TransitionKind {
+    /// Out to black, then in from it
+    Fade,
+    /// The new views fade in over the old ones
+    Crossfade,
+    /// The new views push the old ones off to the left
+    Slide,
+    /// The new views are revealed from left to right
+    Wipe,
+}
+
+/// How ViewAction::TransitionTo changes views
+#[derive(Copy, Clone, Debug)]
+pub struct Transition {
+    pub kind: TransitionKind,
+    pub duration_ns: i64,
+}
+
+impl Transition {
+    pub fn new(kind: TransitionKind, duration_ms: i64) -> Transition {
+        Transition {
+            kind: kind,
+            duration_ns: duration_ms * 1_000_000,
+        }
+    }
+}
+
+/// A transition in progress.  The outgoing views are kept around to be
+/// drawn, but neither they nor the incoming views are updated until it's done.
+pub struct ViewTransition {
+    transition: Transition,
+    elapsed_ns: i64,
+
+    outgoing: ViewStack,
+
+    /// Crossfades and slides draw each set of views to a texture first
+    outgoing_texture: Option<Texture>,
+    incoming_texture: Option<Texture>,
+}
+
+impl ViewTransition {
+    pub fn new(transition: Transition, outgoing: ViewStack) -> ViewTransition {
+        debug!("Starting {:?} transition", transition.kind);
+
+        ViewTransition {
+            transition: transition,
+            elapsed_ns: 0,
+
+            outgoing: outgoing,
+
+            outgoing_texture: None,
+            incoming_texture: None,
+        }
+    }
+
+    /// Returns true once the transition is over
+    pub fn advance(&mut self, elapsed_ns: i64) -> bool {
+        self.elapsed_ns += elapsed_ns;
+        self.elapsed_ns >= self.transition.duration_ns
+    }
+
+    /// From 0 to 1
+    pub fn progress(&self) -> f32 {
+        if self.transition.duration_ns <= 0 {
+            1.0
+        } else {
+            (self.elapsed_ns as f32 / self.transition.duration_ns as f32).min(1.0)
+        }
+    }
+
+    pub fn resize(&mut self, context: &mut Context) {
+        self.outgoing.resize(context);
+
+        // They're the old screen size now
+        self.outgoing_texture = None;
+        self.incoming_texture = None;
+    }
+
+    pub fn draw(&mut self, context: &mut Context, incoming: &ViewStack) {
+        let progress = self.progress();
+        let screen_size = context.screen_size;
+
+        match self.transition.kind {
+            TransitionKind::Fade => self.draw_fade(context, incoming, progress),
+            TransitionKind::Wipe => {
+                graphics::draw_views(context, &self.outgoing);
+
+                let revealed = Rectangle::new(
+                    Vector2::zero(),
+                    Vector2::new(screen_size.x * progress, screen_size.y),
+                ).unwrap();
+
+                context.sdl_renderer.set_clip_rect(Some(revealed.to_sdl_rectangle()));
+                context.sdl_renderer.set_draw_color(Color::RGB(0, 0, 0));
+                context.sdl_renderer.fill_rect(revealed.to_sdl_rectangle());
+                graphics::draw_views(context, incoming);
+                context.sdl_renderer.set_clip_rect(None);
+            },
+            TransitionKind::Crossfade | TransitionKind::Slide => {
+                let outgoing_texture = self.outgoing_texture.take();
+                self.outgoing_texture = render_to_texture(context, &self.outgoing, outgoing_texture);
+
+                let incoming_texture = self.incoming_texture.take();
+                self.incoming_texture = render_to_texture(context, incoming, incoming_texture);
+
+                let (outgoing_texture, incoming_texture) = match (self.outgoing_texture.as_mut(), self.incoming_texture.as_mut()) {
+                    (Some(outgoing_texture), Some(incoming_texture)) => (outgoing_texture, incoming_texture),
+                    _ => {
+                        // No render targets, so do what we can without them
+                        self.draw_fade(context, incoming, progress);
+                        return;
+                    },
+                };
+
+                let screen = Rectangle::new(Vector2::zero(), screen_size).unwrap();
+
+                if self.transition.kind == TransitionKind::Crossfade {
+                    context.sdl_renderer.copy(outgoing_texture, None, Some(screen.to_sdl_rectangle()));
+
+                    incoming_texture.set_blend_mode(BlendMode::Blend);
+                    incoming_texture.set_alpha_mod((progress * 255.0) as u8);
+                    context.sdl_renderer.copy(incoming_texture, None, Some(screen.to_sdl_rectangle()));
+                } else {
+                    let offset = (screen_size.x * progress).round();
+
+                    let mut outgoing_region = screen;
+                    outgoing_region.position.x = -offset;
+                    let mut incoming_region = screen;
+                    incoming_region.position.x = screen_size.x - offset;
+
+                    context.sdl_renderer.copy(outgoing_texture, None, Some(outgoing_region.to_sdl_rectangle()));
+                    context.sdl_renderer.copy(incoming_texture, None, Some(incoming_region.to_sdl_rectangle()));
+                }
+            },
+        }
+    }
+
+    fn draw_fade(&self, context: &mut Context, incoming: &ViewStack, progress: f32) {
+        let darkness = if progress < 0.5 {
+            graphics::draw_views(context, &self.outgoing);
+            progress * 2.0
+        } else {
+            graphics::draw_views(context, incoming);
+            (1.0 - progress) * 2.0
+        };
+
+        let (width, height) = (context.screen_size.x as u32, context.screen_size.y as u32);
+
+        context.sdl_renderer.set_blend_mode(BlendMode::Blend);
+        context.sdl_renderer.set_draw_color(Color::RGBA(0, 0, 0, (darkness * 255.0) as u8));
+        context.sdl_renderer.fill_rect(SdlRectangle::new(0, 0, width, height).unwrap().unwrap());
+        context.sdl_renderer.set_blend_mode(BlendMode::None);
+    }
+}
+
+/// Draws views into a texture the size of the screen, reusing texture if it's
+/// given.  Returns None if the renderer can't draw to textures.
+fn render_to_texture(context: &mut Context, views: &ViewStack, texture: Option<Texture>) -> Option<Texture> {
+    let (width, height) = (context.screen_size.x as u32, context.screen_size.y as u32);
+
+    {
+        let mut target = match context.sdl_renderer.render_target() {
+            Some(target) => target,
+            None => return None,
+        };
+
+        let set = match texture {
+            Some(texture) => target.set(texture).is_ok(),
+            None => target.create_and_set(PixelFormatEnum::RGBA8888, width, height).is_ok(),
+        };
+
+        if !set {
+            return None;
+        }
+    }
+
+    context.sdl_renderer.set_draw_color(Color::RGB(0, 0, 0));
+    context.sdl_renderer.clear();
+    graphics::draw_views(context, views);
+
+    context.sdl_renderer.render_target()
+        .and_then(|mut target| target.reset().ok())
+        .and_then(|texture| texture)
+}